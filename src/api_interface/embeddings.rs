@@ -0,0 +1,304 @@
+use crate::program::errors::EmbeddingError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Literal string embedded once at construction time so the model's output dimensionality can
+/// be inferred instead of requiring the caller to hardcode it.
+const DIMENSION_PROBE: &str = "test";
+
+/// Default cap on in-flight embedding requests when a constructor doesn't specify one.
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// A REST embedding backend, speaking directly to a provider's HTTP API rather than going
+/// through an SDK, so a single implementation covers any server exposing the same endpoint
+/// shape (a local Ollama instance, a hosted OpenAI-compatible gateway, ...). Backs
+/// `Executor::generate_embeddings` only; `ProgramMemory`/`FileSystem` embed through their own,
+/// separately-evolved `memory::files::Embedder` trait instead (see that trait's doc comment for
+/// why there are multiple embedder abstractions in this crate).
+#[async_trait]
+pub trait RestEmbedder: Send + Sync {
+    /// Embeds a single string.
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError>;
+
+    /// Embeds many strings. The default implementation embeds one at a time; implementations
+    /// that support concurrent dispatch should override this.
+    async fn embed_chunks(&self, chunks: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let mut embeddings = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            embeddings.push(self.embed(chunk).await?);
+        }
+        Ok(embeddings)
+    }
+
+    /// The dimensionality of vectors this embedder produces, inferred at construction time.
+    fn dimension(&self) -> usize;
+
+    /// A suggested number of chunks to split a document into when indexing it with this
+    /// embedder, matched to how many requests it can have in flight at once.
+    fn chunk_count_hint(&self) -> usize {
+        DEFAULT_MAX_CONCURRENCY
+    }
+}
+
+/// Embeds against Ollama's `/api/embeddings` endpoint.
+pub struct OllamaRestEmbedder {
+    host: String,
+    model: String,
+    client: Client,
+    dimension: usize,
+    max_concurrency: usize,
+}
+
+impl OllamaRestEmbedder {
+    /// Connects to `host` (e.g. `http://localhost:11434`) and probes `model` with the literal
+    /// string `"test"` to determine its output dimension. Fails with
+    /// `EmbeddingError::ModelDoesNotExist` if the probe request doesn't succeed; the model is
+    /// never auto-pulled. `max_concurrency` bounds how many embedding requests `embed_chunks`
+    /// keeps in flight at once.
+    pub async fn new(
+        host: String,
+        model: String,
+        max_concurrency: usize,
+    ) -> Result<Self, EmbeddingError> {
+        let client = Client::new();
+        let dimension = Self::probe_dimension(&client, &host, &model).await?;
+        Ok(Self {
+            host,
+            model,
+            client,
+            dimension,
+            max_concurrency: max_concurrency.max(1),
+        })
+    }
+
+    async fn probe_dimension(
+        client: &Client,
+        host: &str,
+        model: &str,
+    ) -> Result<usize, EmbeddingError> {
+        let embedding = Self::request(client, host, model, DIMENSION_PROBE)
+            .await
+            .map_err(|_| EmbeddingError::ModelDoesNotExist)?;
+        Ok(embedding.len())
+    }
+
+    async fn request(
+        client: &Client,
+        host: &str,
+        model: &str,
+        text: &str,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let response = client
+            .post(format!("{}/api/embeddings", host.trim_end_matches('/')))
+            .json(&json!({ "model": model, "prompt": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let body: Value = response.json().await?;
+        let embedding = body["embedding"]
+            .as_array()
+            .ok_or("no embedding field in response")?
+            .iter()
+            .map(|v| v.as_f64().unwrap_or_default() as f32)
+            .collect();
+        Ok(embedding)
+    }
+}
+
+#[async_trait]
+impl RestEmbedder for OllamaRestEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        Self::request(&self.client, &self.host, &self.model, text)
+            .await
+            .map_err(|_| EmbeddingError::DocumentEmbedding(text.to_string()))
+    }
+
+    async fn embed_chunks(&self, chunks: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrency));
+        let mut join_set = JoinSet::new();
+
+        for (index, chunk) in chunks.iter().cloned().enumerate() {
+            let semaphore = semaphore.clone();
+            let client = self.client.clone();
+            let host = self.host.clone();
+            let model = self.model.clone();
+            join_set.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let embedding = Self::request(&client, &host, &model, &chunk)
+                    .await
+                    .map_err(|_| EmbeddingError::DocumentEmbedding(chunk));
+                (index, embedding)
+            });
+        }
+
+        collect_in_order(join_set, chunks.len()).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn chunk_count_hint(&self) -> usize {
+        self.max_concurrency
+    }
+}
+
+/// Embeds against an OpenAI-compatible `/v1/embeddings` endpoint (OpenAI itself, or any gateway
+/// that mirrors its request/response shape).
+pub struct OpenAICompatibleEmbedder {
+    base_url: String,
+    api_key: String,
+    model: String,
+    client: Client,
+    dimension: usize,
+    max_concurrency: usize,
+}
+
+impl OpenAICompatibleEmbedder {
+    /// Connects to `base_url` (e.g. `https://api.openai.com`) and probes `model` with the
+    /// literal string `"test"` to determine its output dimension. Fails with
+    /// `EmbeddingError::ModelDoesNotExist` if the probe request doesn't succeed.
+    /// `max_concurrency` bounds how many embedding requests `embed_chunks` keeps in flight
+    /// at once.
+    pub async fn new(
+        base_url: String,
+        api_key: String,
+        model: String,
+        max_concurrency: usize,
+    ) -> Result<Self, EmbeddingError> {
+        let client = Client::new();
+        let dimension = Self::probe_dimension(&client, &base_url, &api_key, &model).await?;
+        Ok(Self {
+            base_url,
+            api_key,
+            model,
+            client,
+            dimension,
+            max_concurrency: max_concurrency.max(1),
+        })
+    }
+
+    async fn probe_dimension(
+        client: &Client,
+        base_url: &str,
+        api_key: &str,
+        model: &str,
+    ) -> Result<usize, EmbeddingError> {
+        let embedding = Self::request(client, base_url, api_key, model, DIMENSION_PROBE)
+            .await
+            .map_err(|_| EmbeddingError::ModelDoesNotExist)?;
+        Ok(embedding.len())
+    }
+
+    async fn request(
+        client: &Client,
+        base_url: &str,
+        api_key: &str,
+        model: &str,
+        text: &str,
+    ) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+        let response = client
+            .post(format!("{}/v1/embeddings", base_url.trim_end_matches('/')))
+            .bearer_auth(api_key)
+            .json(&json!({ "model": model, "input": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let body: Value = response.json().await?;
+        let embedding = body["data"][0]["embedding"]
+            .as_array()
+            .ok_or("no embedding field in response")?
+            .iter()
+            .map(|v| v.as_f64().unwrap_or_default() as f32)
+            .collect();
+        Ok(embedding)
+    }
+}
+
+#[async_trait]
+impl RestEmbedder for OpenAICompatibleEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        Self::request(
+            &self.client,
+            &self.base_url,
+            &self.api_key,
+            &self.model,
+            text,
+        )
+        .await
+        .map_err(|_| EmbeddingError::DocumentEmbedding(text.to_string()))
+    }
+
+    async fn embed_chunks(&self, chunks: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrency));
+        let mut join_set = JoinSet::new();
+
+        for (index, chunk) in chunks.iter().cloned().enumerate() {
+            let semaphore = semaphore.clone();
+            let client = self.client.clone();
+            let base_url = self.base_url.clone();
+            let api_key = self.api_key.clone();
+            let model = self.model.clone();
+            join_set.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let embedding = Self::request(&client, &base_url, &api_key, &model, &chunk)
+                    .await
+                    .map_err(|_| EmbeddingError::DocumentEmbedding(chunk));
+                (index, embedding)
+            });
+        }
+
+        collect_in_order(join_set, chunks.len()).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn chunk_count_hint(&self) -> usize {
+        self.max_concurrency
+    }
+}
+
+/// Drains `join_set`, placing each task's embedding at its original index. On the first error,
+/// aborts every task still in flight and returns that error once the set has drained.
+async fn collect_in_order(
+    mut join_set: JoinSet<(usize, Result<Vec<f32>, EmbeddingError>)>,
+    len: usize,
+) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    let mut embeddings: Vec<Option<Vec<f32>>> = vec![None; len];
+    let mut first_error = None;
+
+    while let Some(result) = join_set.join_next().await {
+        let (index, embedding) = match result {
+            Ok(output) => output,
+            // Tasks cancelled by `abort_all` after the first error have nothing to report.
+            Err(err) if err.is_cancelled() => continue,
+            Err(err) => panic!("embedding task panicked: {err}"),
+        };
+        match embedding {
+            Ok(embedding) => embeddings[index] = Some(embedding),
+            Err(err) => {
+                if first_error.is_none() {
+                    first_error = Some(err);
+                }
+                join_set.abort_all();
+            }
+        }
+    }
+
+    if let Some(err) = first_error {
+        return Err(err);
+    }
+
+    Ok(embeddings
+        .into_iter()
+        .map(|embedding| embedding.expect("every chunk was embedded or the task set aborted"))
+        .collect())
+}
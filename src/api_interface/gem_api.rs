@@ -1,39 +1,308 @@
+use crate::program::atomics::MessageInput;
 use log::warn;
 use ollama_rs::{
     error::OllamaError, generation::functions::tools::Tool,
     generation::functions::OpenAIFunctionCall,
 };
 use reqwest::Client;
+use serde::Deserialize;
 use serde_json::{json, Value};
-use std::{error::Error, sync::Arc};
+use std::{error::Error, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// How a `GeminiExecutor` authenticates its requests.
+#[derive(Debug, Clone)]
+pub enum GeminiAuth {
+    /// Consumer API key, passed as the `key` query parameter against
+    /// `generativelanguage.googleapis.com`.
+    ApiKey(String),
+    /// Vertex AI via a Google service-account credentials (ADC) file. Requests are signed with
+    /// a short-lived OAuth bearer token exchanged from the service account's private key.
+    Vertex {
+        project_id: String,
+        location: String,
+        credentials_path: String,
+    },
+}
+
+/// The subset of a Google service-account JSON credentials file needed to mint access tokens.
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// A cached Vertex AI access token, refreshed shortly before it actually expires.
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+/// Default number of retries (after the first attempt) made against a retryable (429/5xx)
+/// Gemini API error before giving up.
+const DEFAULT_MAX_RETRIES: u32 = 7;
 
 pub struct GeminiExecutor {
     model: String,
-    api_key: String,
+    auth: GeminiAuth,
     client: Client,
     max_tokens: i32,
+    max_retries: u32,
+    token_cache: Mutex<Option<CachedToken>>,
 }
 
 impl GeminiExecutor {
     pub fn new(model: String, api_key: String, max_tokens: i32) -> Self {
         Self {
             model,
-            api_key,
+            auth: GeminiAuth::ApiKey(api_key),
             client: Client::new(),
             max_tokens,
+            max_retries: DEFAULT_MAX_RETRIES,
+            token_cache: Mutex::new(None),
+        }
+    }
+
+    /// Creates a `GeminiExecutor` that targets the regional Vertex AI endpoint and
+    /// authenticates via a service-account credentials file instead of a raw API key.
+    pub fn new_vertex(
+        model: String,
+        project_id: String,
+        location: String,
+        credentials_path: String,
+        max_tokens: i32,
+    ) -> Self {
+        Self {
+            model,
+            auth: GeminiAuth::Vertex {
+                project_id,
+                location,
+                credentials_path,
+            },
+            client: Client::new(),
+            max_tokens,
+            max_retries: DEFAULT_MAX_RETRIES,
+            token_cache: Mutex::new(None),
+        }
+    }
+
+    /// Overrides the number of retries (after the first attempt) made against a retryable
+    /// (429/5xx) error before `generate_text`/`function_call` give up with an `OllamaError`
+    /// reading "Gemini API gave up after N retries". That error is not an
+    /// `ExecutionError::RetriesExhausted`; `Executor` reports it as one only when
+    /// `Config.max_retries` is set.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Builds the `generateContent` URL for the configured auth mode, with no query string
+    /// for Vertex (auth goes in the `Authorization` header instead).
+    fn endpoint_url(&self) -> String {
+        match &self.auth {
+            GeminiAuth::ApiKey(api_key) => format!(
+                "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+                self.model, api_key
+            ),
+            GeminiAuth::Vertex {
+                project_id,
+                location,
+                ..
+            } => format!(
+                "https://{location}-aiplatform.googleapis.com/v1/projects/{project_id}/locations/{location}/publishers/google/models/{model}:generateContent",
+                location = location,
+                project_id = project_id,
+                model = self.model
+            ),
+        }
+    }
+
+    /// Returns a bearer token to attach for Vertex AI requests, refreshing it if the cached
+    /// one has expired. Returns `None` for `GeminiAuth::ApiKey`, which authenticates via the
+    /// `key` query parameter instead.
+    async fn bearer_token(&self) -> Result<Option<String>, OllamaError> {
+        let GeminiAuth::Vertex {
+            credentials_path, ..
+        } = &self.auth
+        else {
+            return Ok(None);
+        };
+
+        let mut cache = self.token_cache.lock().await;
+        if let Some(cached) = cache.as_ref() {
+            if cached.expires_at > Instant::now() {
+                return Ok(Some(cached.access_token.clone()));
+            }
+        }
+
+        let token = self.fetch_service_account_token(credentials_path).await?;
+        let access_token = token.0;
+        *cache = Some(CachedToken {
+            access_token: access_token.clone(),
+            // Refresh a minute early so a request never races a just-expired token.
+            expires_at: Instant::now() + Duration::from_secs(token.1.saturating_sub(60)),
+        });
+        Ok(Some(access_token))
+    }
+
+    /// Exchanges the service account's private key for a short-lived access token via a
+    /// signed JWT assertion against the credentials' `token_uri`.
+    async fn fetch_service_account_token(
+        &self,
+        credentials_path: &str,
+    ) -> Result<(String, u64), OllamaError> {
+        let raw = std::fs::read_to_string(credentials_path).map_err(|e| {
+            OllamaError::from(format!(
+                "Failed to read service account credentials at {}: {}",
+                credentials_path, e
+            ))
+        })?;
+        let key: ServiceAccountKey = serde_json::from_str(&raw).map_err(|e| {
+            OllamaError::from(format!("Invalid service account credentials JSON: {}", e))
+        })?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let claims = json!({
+            "iss": key.client_email,
+            "scope": "https://www.googleapis.com/auth/cloud-platform",
+            "aud": key.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+
+        let header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+        let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|e| {
+                OllamaError::from(format!("Invalid service account private key: {}", e))
+            })?;
+        let assertion = jsonwebtoken::encode(&header, &claims, &encoding_key)
+            .map_err(|e| OllamaError::from(format!("Failed to sign JWT assertion: {}", e)))?;
+
+        let response = self
+            .client
+            .post(&key.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| OllamaError::from(format!("Token exchange request failed: {:?}", e)))?;
+
+        if let Err(e) = response.error_for_status_ref() {
+            return Err(OllamaError::from(format!(
+                "Token exchange failed with status {}: {:?}",
+                response.status(),
+                e
+            )));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| OllamaError::from(format!("Failed to parse token response: {:?}", e)))?;
+        Ok((token.access_token, token.expires_in))
+    }
+
+    /// POSTs `body` to `url`, retrying retryable responses (HTTP 429 or 5xx) with exponential
+    /// backoff and jitter, honoring a `Retry-After` header when present. Non-retryable 4xx
+    /// (400/401/403/...) fail immediately; exhausting `max_retries` fails with an `OllamaError`
+    /// reading "Gemini API gave up after N retries (status ...)".
+    async fn post_with_retry(&self, url: &str, body: &Value) -> Result<Value, OllamaError> {
+        let mut attempt = 0;
+        loop {
+            let bearer_token = self.bearer_token().await?;
+            let mut request = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json");
+            if let Some(token) = &bearer_token {
+                request = request.bearer_auth(token);
+            }
+            let response = request.json(body).send().await.map_err(|e| {
+                OllamaError::from(format!("Gemini API request failed: {:?}", e.source()))
+            })?;
+
+            let status = response.status();
+            if status.is_success() {
+                return response.json().await.map_err(|e| {
+                    OllamaError::from(format!("Failed to parse Gemini API response: {}", e))
+                });
+            }
+
+            let retryable = status.as_u16() == 429 || status.is_server_error();
+            if !retryable || attempt >= self.max_retries {
+                let detail = format!(
+                    "status {}: {}",
+                    status,
+                    response.text().await.unwrap_or_default()
+                );
+                if retryable {
+                    return Err(OllamaError::from(format!(
+                        "Gemini API gave up after {} retries ({})",
+                        attempt, detail
+                    )));
+                }
+                return Err(OllamaError::from(format!(
+                    "Gemini API request failed with {}",
+                    detail
+                )));
+            }
+
+            let delay = Self::retry_delay(&response, attempt);
+            warn!(
+                "Gemini API returned {}, retrying in {:?} (attempt {}/{})",
+                status,
+                delay,
+                attempt + 1,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
         }
     }
 
+    /// Computes how long to wait before the next retry: the response's `Retry-After` header if
+    /// present (seconds or an HTTP-date), otherwise exponential backoff with jitter.
+    fn retry_delay(response: &reqwest::Response, attempt: u32) -> Duration {
+        if let Some(retry_after) = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+        {
+            if let Ok(secs) = retry_after.parse::<u64>() {
+                return Duration::from_secs(secs);
+            }
+            if let Ok(when) = httpdate::parse_http_date(retry_after) {
+                if let Ok(remaining) = when.duration_since(std::time::SystemTime::now()) {
+                    return remaining;
+                }
+            }
+        }
+
+        let base_ms = 500u64.saturating_mul(1u64 << attempt.min(10));
+        let jitter_ms = rand::random::<u64>() % 250;
+        Duration::from_millis(base_ms + jitter_ms)
+    }
+
     // now supports structured output
     pub async fn generate_text(
         &self,
         prompt: &str,
         schema: &Option<String>,
     ) -> Result<String, OllamaError> {
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.model, self.api_key
-        );
+        let url = self.endpoint_url();
 
         let mut generation_config = json!({
             "temperature": 1.0,
@@ -73,29 +342,7 @@ impl GeminiExecutor {
             "generationConfig": generation_config
         });
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| {
-                OllamaError::from(format!("Gemini API request failed: {:?}", e.source()))
-            })?;
-
-        // check status
-        if let Err(e) = response.error_for_status_ref() {
-            return Err(OllamaError::from(format!(
-                "Gemini API request failed with status {}: {:?}",
-                response.status(),
-                e.source()
-            )));
-        }
-
-        let response_body: Value = response.json().await.map_err(|e| {
-            OllamaError::from(format!("Failed to parse Gemini API response: {}", e))
-        })?;
+        let response_body = self.post_with_retry(&url, &body).await?;
 
         self.extract_generated_text(response_body)
     }
@@ -132,15 +379,12 @@ impl GeminiExecutor {
 
     pub async fn function_call(
         &self,
-        prompt: &str,
+        prompt: Vec<MessageInput>,
         tools: Vec<Arc<dyn Tool>>,
         raw_mode: bool,
         oai_parser: Arc<OpenAIFunctionCall>,
     ) -> Result<String, OllamaError> {
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.model, self.api_key
-        );
+        let url = self.endpoint_url();
 
         let function_declarations: Vec<Value> = tools
             .iter()
@@ -153,6 +397,24 @@ impl GeminiExecutor {
             })
             .collect();
 
+        // Carries the entire accumulated history (the original prompt plus any prior
+        // tool-call/tool-result turns), not just the latest message, so the multi-step agentic
+        // loop (`Executor::agentic_function_call`) re-queries with context instead of the model
+        // seeing only the most recent "Tool results: ..." turn in isolation. Gemini has no
+        // "assistant" role, so non-user turns map to "model".
+        let contents: Vec<Value> = prompt
+            .iter()
+            .map(|msg| {
+                let role = if msg.role == "user" { "user" } else { "model" };
+                json!({
+                    "role": role,
+                    "parts": {
+                        "text": msg.content
+                    }
+                })
+            })
+            .collect();
+
         let body = json!({
             "system_instruction": {
                 "parts": {
@@ -163,37 +425,10 @@ impl GeminiExecutor {
             "tool_config": {
                 "function_calling_config": {"mode": "ANY"}
             },
-            "contents": {
-                "role": "user",
-                "parts": {
-                    "text": prompt
-                }
-            }
+            "contents": contents
         });
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| {
-                OllamaError::from(format!("Gemini API request failed: {:?}", e.source()))
-            })?;
-
-        // check status
-        if let Err(e) = response.error_for_status_ref() {
-            return Err(OllamaError::from(format!(
-                "Gemini API request failed with status {}: {:?}",
-                response.status(),
-                e.source()
-            )));
-        }
-
-        let response_body: Value = response.json().await.map_err(|e| {
-            OllamaError::from(format!("Failed to parse Gemini API response: {:?}", e))
-        })?;
+        let response_body = self.post_with_retry(&url, &body).await?;
 
         let tool_call = self.extract_tools(response_body)?;
 
@@ -221,7 +456,7 @@ impl GeminiExecutor {
                 return match res {
                     Ok(result) => Ok(result.message.unwrap().content),
                     Err(e) => Err(OllamaError::from(format!(
-                        "Could not generate text: {:?}",
+                        "Gemini: could not generate text: {:?}",
                         e
                     ))),
                 };
@@ -229,7 +464,7 @@ impl GeminiExecutor {
         }
 
         Err(OllamaError::from(format!(
-            "No matching tool found for function: {}",
+            "Gemini: no matching tool found for function: {}",
             tool_call["name"].as_str().unwrap_or("")
         )))
     }
@@ -0,0 +1,278 @@
+use crate::api_interface::gem_api::GeminiExecutor;
+use crate::api_interface::open_router::{OpenRouterExecutor, OpenRouterToolChoice};
+use crate::api_interface::openai_api::OpenAIExecutor;
+use crate::program::atomics::{MessageInput, ToolChoice};
+use crate::program::models::Model;
+use async_trait::async_trait;
+use ollama_rs::{
+    error::OllamaError,
+    generation::chat::request::ChatMessageRequest,
+    generation::chat::ChatMessage,
+    generation::completion::request::GenerationRequest,
+    generation::functions::tools::Tool,
+    generation::functions::{FunctionCallRequest, LlamaFunctionCall, OpenAIFunctionCall},
+    generation::options::GenerationOptions,
+    generation::parameters::FormatType,
+    Ollama,
+};
+use std::sync::Arc;
+
+/// A chat/completion backend that `Executor` can dispatch to without knowing which provider
+/// (Gemini, OpenAI-compatible, OpenRouter, Ollama, ...) it actually talks to. Each
+/// implementation owns its own request/response JSON shape rather than forcing a
+/// lowest-common-denominator struct, so a newly released model on a provider works without a
+/// crate change as long as that provider's request envelope is unchanged.
+#[async_trait]
+pub trait LanguageModelProvider: Send + Sync {
+    /// Generates a single completion for `prompt`, optionally constrained by a JSON `schema`.
+    async fn generate_text(
+        &self,
+        prompt: Vec<MessageInput>,
+        schema: Option<&str>,
+    ) -> Result<String, OllamaError>;
+
+    /// Resolves a tool call for `prompt` against `tools`. `raw_mode` returns the call verbatim
+    /// instead of running the tool and returning its result. `tool_choice`, when set, steers
+    /// which tool(s) the model is allowed/required to call; providers that have no native way
+    /// to express it may ignore it (the caller's `get_tools` filtering for `ToolChoice::Force`
+    /// already narrows `tools` regardless).
+    async fn function_call(
+        &self,
+        prompt: Vec<MessageInput>,
+        tools: Vec<Arc<dyn Tool>>,
+        raw_mode: bool,
+        oai_parser: Arc<OpenAIFunctionCall>,
+        tool_choice: Option<&ToolChoice>,
+    ) -> Result<String, OllamaError>;
+}
+
+/// Takes the content of the last message as a flat prompt string, for providers/code paths that
+/// only support a single-turn prompt (Gemini's `generate_text`, and Ollama's raw-completion text
+/// models in `OllamaProvider::generate_text`, neither of which accumulate chat history).
+fn last_message(prompt: &[MessageInput]) -> &str {
+    prompt
+        .last()
+        .map(|msg| msg.content.as_str())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl LanguageModelProvider for GeminiExecutor {
+    async fn generate_text(
+        &self,
+        prompt: Vec<MessageInput>,
+        schema: Option<&str>,
+    ) -> Result<String, OllamaError> {
+        GeminiExecutor::generate_text(self, last_message(&prompt), &schema.map(String::from)).await
+    }
+
+    async fn function_call(
+        &self,
+        prompt: Vec<MessageInput>,
+        tools: Vec<Arc<dyn Tool>>,
+        raw_mode: bool,
+        oai_parser: Arc<OpenAIFunctionCall>,
+        _tool_choice: Option<&ToolChoice>,
+    ) -> Result<String, OllamaError> {
+        // Gemini's function declarations have no native tool_choice equivalent; `get_tools`'s
+        // `ToolChoice::Force` filtering is the only lever available for this provider.
+        GeminiExecutor::function_call(self, prompt, tools, raw_mode, oai_parser).await
+    }
+}
+
+#[async_trait]
+impl LanguageModelProvider for OpenAIExecutor {
+    async fn generate_text(
+        &self,
+        prompt: Vec<MessageInput>,
+        schema: Option<&str>,
+    ) -> Result<String, OllamaError> {
+        OpenAIExecutor::generate_text(self, prompt, schema.map(String::from).as_ref()).await
+    }
+
+    async fn function_call(
+        &self,
+        prompt: Vec<MessageInput>,
+        tools: Vec<Arc<dyn Tool>>,
+        raw_mode: bool,
+        oai_parser: Arc<OpenAIFunctionCall>,
+        tool_choice: Option<&ToolChoice>,
+    ) -> Result<String, OllamaError> {
+        OpenAIExecutor::function_call(self, prompt, tools, raw_mode, oai_parser, tool_choice).await
+    }
+}
+
+#[async_trait]
+impl LanguageModelProvider for OpenRouterExecutor {
+    async fn generate_text(
+        &self,
+        prompt: Vec<MessageInput>,
+        schema: Option<&str>,
+    ) -> Result<String, OllamaError> {
+        // Reasoning mode is a per-model concern (`Model::has_reasoning`); callers that want it
+        // still go through `OpenRouterExecutor::generate_text` directly.
+        OpenRouterExecutor::generate_text(self, prompt, schema.map(String::from).as_ref(), None)
+            .await
+    }
+
+    async fn function_call(
+        &self,
+        prompt: Vec<MessageInput>,
+        tools: Vec<Arc<dyn Tool>>,
+        raw_mode: bool,
+        oai_parser: Arc<OpenAIFunctionCall>,
+        tool_choice: Option<&ToolChoice>,
+    ) -> Result<String, OllamaError> {
+        let tool_choice = tool_choice.map(|choice| match choice {
+            ToolChoice::Auto => OpenRouterToolChoice::Auto,
+            ToolChoice::None => OpenRouterToolChoice::None,
+            ToolChoice::Required => OpenRouterToolChoice::Required,
+            ToolChoice::Force(name) => OpenRouterToolChoice::Function(name.clone()),
+        });
+        OpenRouterExecutor::function_call(self, prompt, tools, raw_mode, oai_parser, tool_choice)
+            .await
+    }
+}
+
+/// Wraps a local `Ollama` instance as a `LanguageModelProvider`, preserving the per-model
+/// special-casing (raw-completion models, `LlamaFunctionCall` vs `OpenAIFunctionCall` parsing)
+/// that previously lived inline in `Executor`.
+pub struct OllamaProvider<'a> {
+    pub llm: &'a Ollama,
+    pub model: Model,
+    pub max_tokens: i32,
+    /// Ollama's context window size (`num_ctx`), in tokens. `None` leaves Ollama's own default
+    /// (4096) in place, which can silently truncate long workflow prompts.
+    pub num_ctx: Option<u64>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    /// Fixes the sampling seed so identical prompts produce identical completions.
+    pub seed: Option<i32>,
+}
+
+impl<'a> OllamaProvider<'a> {
+    /// Builds `GenerationOptions` from `max_tokens` plus whichever of `num_ctx`/`temperature`/
+    /// `top_p`/`seed` the caller's `Config` set, shared by both `generate_text` and
+    /// `function_call` so the runtime options behave identically across both request kinds.
+    fn generation_options(&self) -> GenerationOptions {
+        let mut options = GenerationOptions::default().num_predict(self.max_tokens);
+        if let Some(num_ctx) = self.num_ctx {
+            options = options.num_ctx(num_ctx);
+        }
+        if let Some(temperature) = self.temperature {
+            options = options.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            options = options.top_p(top_p);
+        }
+        if let Some(seed) = self.seed {
+            options = options.seed(seed);
+        }
+        options
+    }
+}
+
+#[async_trait]
+impl<'a> LanguageModelProvider for OllamaProvider<'a> {
+    async fn generate_text(
+        &self,
+        prompt: Vec<MessageInput>,
+        schema: Option<&str>,
+    ) -> Result<String, OllamaError> {
+        match self.model {
+            Model::Llama3_1_8BTextQ4KM
+            | Model::Llama3_1_8BTextQ8
+            | Model::Llama3_1_70BTextQ4KM
+            | Model::Llama3_2_1BTextQ4KM => {
+                let text_prompt = last_message(&prompt);
+                let mut request =
+                    GenerationRequest::new(self.model.to_string(), text_prompt.to_string());
+                request = request.options(self.generation_options());
+
+                let result = self.llm.generate(request).await?;
+                Ok(result.response)
+            }
+            _ => {
+                let mut messages: Vec<ChatMessage> = prompt
+                    .iter()
+                    .map(|msg| match msg.role.as_str() {
+                        "user" => ChatMessage::user(msg.content.clone()),
+                        "assistant" => ChatMessage::assistant(msg.content.clone()),
+                        _ => ChatMessage::user(msg.content.clone()),
+                    })
+                    .collect();
+
+                let mut request = if let Some(schema) = schema {
+                    // `schema` is base64-encoded by workflow authors so it can carry arbitrary
+                    // JSON/text through the `Task` schema field without escaping concerns.
+                    let decoded_schema =
+                        match base64::prelude::BASE64_STANDARD.decode(schema.as_bytes()) {
+                            Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                            Err(e) => {
+                                return Err(OllamaError::from(format!(
+                                    "Schema format invalid, failed to decode base64: {}",
+                                    e
+                                )))
+                            }
+                        };
+                    messages.insert(0, ChatMessage::assistant(decoded_schema));
+                    ChatMessageRequest::new(self.model.to_string(), messages)
+                        .format(FormatType::Json)
+                } else {
+                    ChatMessageRequest::new(self.model.to_string(), messages)
+                };
+                request = request.options(self.generation_options());
+
+                let result = self.llm.send_chat_messages(request).await?;
+                Ok(result.message.unwrap().content)
+            }
+        }
+    }
+
+    async fn function_call(
+        &self,
+        prompt: Vec<MessageInput>,
+        tools: Vec<Arc<dyn Tool>>,
+        raw_mode: bool,
+        oai_parser: Arc<OpenAIFunctionCall>,
+        _tool_choice: Option<&ToolChoice>,
+    ) -> Result<String, OllamaError> {
+        // `FunctionCallRequest` has no tool_choice equivalent beyond `raw_mode`; `get_tools`'s
+        // `ToolChoice::Force` filtering is the only lever available for this provider.
+        //
+        // Carries the entire accumulated history (the original prompt plus any prior
+        // tool-call/tool-result turns), not just the latest message, so the multi-step agentic
+        // loop (`Executor::agentic_function_call`) re-queries with context instead of the model
+        // seeing only the most recent "Tool results: ..." turn in isolation.
+        let messages: Vec<ChatMessage> = prompt
+            .iter()
+            .map(|msg| match msg.role.as_str() {
+                "assistant" => ChatMessage::assistant(msg.content.clone()),
+                _ => ChatMessage::user(msg.content.clone()),
+            })
+            .collect();
+        let mut request = FunctionCallRequest::new(self.model.to_string(), tools, messages)
+            .options(self.generation_options());
+        if raw_mode {
+            request = request.raw_mode();
+        }
+
+        let result = self
+            .llm
+            .send_function_call(
+                request,
+                match self.model {
+                    Model::NousTheta
+                    | Model::Llama3_1_8B
+                    | Model::Llama3_1_8Bf16
+                    | Model::Llama3_1_8Bq8
+                    | Model::Llama3_2_3B
+                    | Model::Llama3_1_70Bq8
+                    | Model::Llama3_1_70B => Arc::new(LlamaFunctionCall {}),
+                    _ => oai_parser,
+                },
+            )
+            .await?;
+        Ok(result.message.unwrap().content)
+    }
+}
@@ -1,33 +1,111 @@
 use std::sync::Arc;
 
 use crate::program::atomics::MessageInput;
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt};
+use futures::Stream;
 use ollama_rs::error::OllamaError;
 use ollama_rs::{generation::functions::tools::Tool, generation::functions::OpenAIFunctionCall};
 use reqwest::{header, Client};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-/// [Reasoning](https://openrouter.ai/docs/use-cases/reasoning-tokens) setting for [`OpenRouterRequest`].
-#[derive(Debug, Serialize)]
-struct OpenRouterReasoning {
+/// Maximum number of model round-trips `function_call` will make while feeding tool results
+/// back, before giving up without a final response.
+const DEFAULT_MAX_FUNCTION_CALL_STEPS: u32 = 5;
+
+/// [Reasoning](https://openrouter.ai/docs/use-cases/reasoning-tokens) setting for
+/// [`OpenRouterRequest`].
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenRouterReasoning {
     /// Maximum number of tokens to use for reasoning, Anthropic style.
-    max_tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// OpenAI-style effort level, as an alternative to a token budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<OpenRouterReasoningEffort>,
+    /// If `true`, the model still reasons internally but the reasoning is omitted from the
+    /// response, so callers never see `<think>` content for it.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub exclude: bool,
 }
 
 impl Default for OpenRouterReasoning {
     fn default() -> Self {
-        Self { max_tokens: 2000 }
+        Self {
+            max_tokens: Some(2000),
+            effort: None,
+            exclude: false,
+        }
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenRouterReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
 #[derive(Debug, Serialize)]
 struct OpenRouterRequest {
     model: String,
     messages: Vec<OpenRouterMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenRouterTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<OpenRouterToolChoice>,
     /// If `Some`, the model will return reasoning data.
     reasoning: Option<OpenRouterReasoning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<Value>,
+    stream: bool,
+}
+
+/// OpenRouter's accepted `tool_choice` request shapes: let the model decide, forbid tool use,
+/// require some tool call, or force one specific tool by name.
+#[derive(Debug, Clone)]
+pub enum OpenRouterToolChoice {
+    Auto,
+    None,
+    Required,
+    Function(String),
+}
+
+impl Serialize for OpenRouterToolChoice {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            OpenRouterToolChoice::Auto => serializer.serialize_str("auto"),
+            OpenRouterToolChoice::None => serializer.serialize_str("none"),
+            OpenRouterToolChoice::Required => serializer.serialize_str("required"),
+            OpenRouterToolChoice::Function(name) => json!({
+                "type": "function",
+                "function": { "name": name }
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+/// A single incremental chunk of an SSE-streamed chat completion.
+#[derive(Debug, Deserialize)]
+struct OpenRouterStreamChunk {
+    choices: Vec<OpenRouterStreamChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterStreamChoice {
+    delta: OpenRouterStreamDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterStreamDelta {
+    content: Option<String>,
+    reasoning: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -50,6 +128,10 @@ struct OpenRouterMessage {
     reasoning: Option<String>, // returned with reasoning models like DeepSeek-R1
     refusal: Option<String>,
     tool_calls: Option<Vec<OpenRouterToolCall>>,
+    /// Set on `role: "tool"` messages to associate a tool's result with the `OpenRouterToolCall`
+    /// that requested it, per OpenRouter's (OpenAI-compatible) agentic tool-calling protocol.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -112,9 +194,10 @@ impl OpenRouterExecutor {
     pub async fn generate_text(
         &self,
         input: Vec<MessageInput>,
-        _schema: Option<&String>,
-        with_reasoning: bool,
+        schema: Option<&String>,
+        reasoning: Option<OpenRouterReasoning>,
     ) -> Result<String, OllamaError> {
+        let exclude_reasoning = reasoning.as_ref().is_some_and(|r| r.exclude);
         let messages: Vec<OpenRouterMessage> = input
             .into_iter()
             .map(|msg| OpenRouterMessage {
@@ -123,18 +206,33 @@ impl OpenRouterExecutor {
                 reasoning: None,
                 refusal: None,
                 tool_calls: None,
+                tool_call_id: None,
             })
             .collect();
 
+        let response_format = schema
+            .map(|schema| {
+                let schema: Value = serde_json::from_str(schema)
+                    .map_err(|e| OllamaError::from(format!("Invalid schema JSON: {:?}", e)))?;
+                Ok::<_, OllamaError>(json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "structured_output",
+                        "strict": true,
+                        "schema": schema
+                    }
+                }))
+            })
+            .transpose()?;
+
         let request = OpenRouterRequest {
             model: self.model.clone(),
             messages,
             tools: None,
-            reasoning: if with_reasoning {
-                Some(OpenRouterReasoning::default())
-            } else {
-                None
-            },
+            tool_choice: None,
+            reasoning,
+            response_format,
+            stream: false,
         };
 
         let mut headers = header::HeaderMap::new();
@@ -170,7 +268,12 @@ impl OpenRouterExecutor {
                 .ok_or_else(|| OllamaError::from("No content in response".to_string()))
                 .map(|s| s.to_string())?;
 
-            match choice.message.reasoning.as_ref().map(|s| s.to_string()) {
+            match choice
+                .message
+                .reasoning
+                .as_ref()
+                .filter(|_| !exclude_reasoning)
+            {
                 // if there is a reasoning, return it with the `think` tags, followed by the content
                 Some(reasoning) => Ok(format!("<think>\n{}\n</think>\n\n{}", reasoning, content)),
                 // otherwise just return the content
@@ -181,12 +284,84 @@ impl OpenRouterExecutor {
         }
     }
 
+    /// Streaming counterpart of [`Self::generate_text`]. Sets `stream: true` on the request and
+    /// returns a stream of content chunks as they're parsed out of the response's SSE frames,
+    /// instead of blocking until the full completion is ready. Reasoning deltas arrive wrapped
+    /// in the same `<think>…</think>` convention `generate_text` uses for a complete response.
+    pub fn generate_text_stream(
+        &self,
+        input: Vec<MessageInput>,
+        reasoning: Option<OpenRouterReasoning>,
+    ) -> BoxStream<'static, Result<String, OllamaError>> {
+        let exclude_reasoning = reasoning.as_ref().is_some_and(|r| r.exclude);
+
+        let messages: Vec<OpenRouterMessage> = input
+            .into_iter()
+            .map(|msg| OpenRouterMessage {
+                role: msg.role,
+                content: Some(msg.content),
+                reasoning: None,
+                refusal: None,
+                tool_calls: None,
+                tool_call_id: None,
+            })
+            .collect();
+
+        let request = OpenRouterRequest {
+            model: self.model.clone(),
+            messages,
+            tools: None,
+            tool_choice: None,
+            reasoning,
+            response_format: None,
+            stream: true,
+        };
+
+        let client = self.client.clone();
+        let api_key = self.api_key.clone();
+
+        let response = async move {
+            let mut headers = header::HeaderMap::new();
+            headers.insert(
+                "Authorization",
+                header::HeaderValue::from_str(&format!("Bearer {}", api_key))
+                    .map_err(|e| OllamaError::from(format!("Invalid header value: {}", e)))?,
+            );
+            headers.insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json"),
+            );
+
+            client
+                .post("https://openrouter.ai/api/v1/chat/completions")
+                .headers(headers)
+                .json(&request)
+                .send()
+                .await
+                .map_err(|e| OllamaError::from(format!("Failed to send request: {}", e)))
+        };
+
+        stream::once(response)
+            .flat_map(move |response| match response {
+                Ok(response) => sse_content_stream(response.bytes_stream(), exclude_reasoning),
+                Err(e) => stream::once(async move { Err(e) }).boxed(),
+            })
+            .boxed()
+    }
+
+    /// Runs an agentic tool-calling loop: as long as the model keeps returning `tool_calls`,
+    /// each requested tool is executed and its result is fed back as a `role: "tool"` message,
+    /// then the full history is re-sent. Stops once the model responds with no `tool_calls`
+    /// (returning its final `content`) or after `DEFAULT_MAX_FUNCTION_CALL_STEPS` round-trips,
+    /// whichever comes first. `raw_mode` short-circuits the first step, returning the requested
+    /// calls verbatim instead of executing them.
     pub async fn function_call(
         &self,
-        prompt: &str,
+        prompt: Vec<MessageInput>,
         tools: Vec<Arc<dyn Tool>>,
         raw_mode: bool,
         oai_parser: Arc<OpenAIFunctionCall>,
+        tool_choice: Option<OpenRouterToolChoice>,
     ) -> Result<String, OllamaError> {
         let openai_tools: Vec<_> = tools
             .iter()
@@ -200,19 +375,73 @@ impl OpenRouterExecutor {
             })
             .collect();
 
-        let messages = vec![OpenRouterMessage {
-            role: "user".to_string(),
-            content: Some(prompt.to_string()),
-            refusal: None,
-            reasoning: None, // we dont make use of returned reasoning data
-            tool_calls: None,
-        }];
+        // Seeds the full accumulated history (original prompt plus any prior tool-call/result
+        // turns), not just the latest message, so the multi-step agentic loop actually re-queries
+        // with context instead of the model seeing only the most recent "Tool results: ..." turn.
+        let mut messages: Vec<OpenRouterMessage> = prompt
+            .into_iter()
+            .map(|msg| OpenRouterMessage {
+                role: msg.role,
+                content: Some(msg.content),
+                refusal: None,
+                reasoning: None, // we dont make use of returned reasoning data
+                tool_calls: None,
+                tool_call_id: None,
+            })
+            .collect();
+
+        for _ in 0..DEFAULT_MAX_FUNCTION_CALL_STEPS {
+            let message = self
+                .send_function_call_request(&messages, openai_tools.clone(), tool_choice.clone())
+                .await?;
+
+            let Some(tool_calls) = message.tool_calls.clone() else {
+                return message
+                    .content
+                    .ok_or_else(|| OllamaError::from("No content in response".to_string()));
+            };
+
+            if raw_mode {
+                return self.handle_raw_mode(message);
+            }
+
+            messages.push(message);
+
+            let results = self
+                .handle_normal_mode(tool_calls, &tools, oai_parser.clone())
+                .await?;
+            for (tool_call_id, content) in results {
+                messages.push(OpenRouterMessage {
+                    role: "tool".to_string(),
+                    content: Some(content),
+                    reasoning: None,
+                    refusal: None,
+                    tool_calls: None,
+                    tool_call_id: Some(tool_call_id),
+                });
+            }
+        }
 
+        Err(OllamaError::from(format!(
+            "OpenRouter: exceeded max_steps ({}) of agentic tool calling without a final response",
+            DEFAULT_MAX_FUNCTION_CALL_STEPS
+        )))
+    }
+
+    async fn send_function_call_request(
+        &self,
+        messages: &[OpenRouterMessage],
+        openai_tools: Vec<OpenRouterTool>,
+        tool_choice: Option<OpenRouterToolChoice>,
+    ) -> Result<OpenRouterMessage, OllamaError> {
         let request = OpenRouterRequest {
             model: self.model.clone(),
-            messages,
+            messages: messages.to_vec(),
             tools: Some(openai_tools),
+            tool_choice,
             reasoning: None,
+            response_format: None,
+            stream: false,
         };
 
         let mut headers = header::HeaderMap::new();
@@ -248,12 +477,12 @@ impl OpenRouterExecutor {
                 ))
             })?;
 
-        if raw_mode {
-            self.handle_raw_mode(response_body.choices[0].message.clone())
-        } else {
-            self.handle_normal_mode(response_body.choices[0].message.clone(), tools, oai_parser)
-                .await
-        }
+        response_body
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| OllamaError::from("No response generated".to_string()))
     }
 
     fn handle_raw_mode(&self, message: OpenRouterMessage) -> Result<String, OllamaError> {
@@ -272,43 +501,153 @@ impl OpenRouterExecutor {
         Ok(raw_calls.join("\n\n"))
     }
 
+    /// Executes every requested tool call concurrently and returns each result paired with the
+    /// `tool_call.id` it answers (in the original call order), so the caller can feed them back
+    /// to the model as `role: "tool"` messages. A model emitting several parallel tool calls
+    /// (e.g. scraping two URLs) pays the slowest single call's latency instead of their sum. Any
+    /// single tool error short-circuits the whole batch.
     async fn handle_normal_mode(
         &self,
-        message: OpenRouterMessage,
-        tools: Vec<Arc<dyn Tool>>,
+        tool_calls: Vec<OpenRouterToolCall>,
+        tools: &[Arc<dyn Tool>],
         oai_parser: Arc<OpenAIFunctionCall>,
-    ) -> Result<String, OllamaError> {
-        let mut results = Vec::<String>::new();
+    ) -> Result<Vec<(String, String)>, OllamaError> {
+        let mut calls = Vec::with_capacity(tool_calls.len());
+
+        for tool_call in tool_calls {
+            let matched_tool = tools.iter().find(|tool| {
+                tool.name().to_lowercase().replace(' ', "_") == tool_call.function.name
+            });
+
+            let Some(tool) = matched_tool else {
+                return Err(OllamaError::from(format!(
+                    "OpenRouter: no matching tool found for function: {}",
+                    tool_call.function.name
+                )));
+            };
+
+            let tool_params: Value = serde_json::from_str(&tool_call.function.arguments)?;
+            calls.push((
+                tool_call.id,
+                tool_call.function.name,
+                tool_params,
+                tool.clone(),
+            ));
+        }
 
-        if let Some(tool_calls) = message.tool_calls {
-            for tool_call in tool_calls {
-                for tool in &tools {
-                    if tool.name().to_lowercase().replace(' ', "_") == tool_call.function.name {
-                        let tool_params: Value =
-                            serde_json::from_str(&tool_call.function.arguments)?;
-                        let res = oai_parser
-                            .function_call_with_history(
-                                tool_call.function.name.clone(),
-                                tool_params,
-                                tool.clone(),
-                            )
-                            .await;
-                        match res {
-                            Ok(result) => results.push(result.message.unwrap().content),
-                            Err(e) => {
-                                return Err(OllamaError::from(format!(
-                                    "Could not generate text: {:?}",
+        let calls = calls
+            .into_iter()
+            .map(|(tool_call_id, function_name, tool_params, tool)| {
+                let oai_parser = oai_parser.clone();
+                async move {
+                    let res = oai_parser
+                        .function_call_with_history(function_name, tool_params, tool)
+                        .await;
+                    match res {
+                        Ok(result) => Ok((tool_call_id, result.message.unwrap().content)),
+                        Err(e) => Err(OllamaError::from(format!(
+                            "OpenRouter: could not generate text: {:?}",
+                            e
+                        ))),
+                    }
+                }
+            });
+
+        future::try_join_all(calls).await
+    }
+}
+
+/// Turns a raw SSE byte stream from OpenRouter into a stream of content chunks: skips empty
+/// lines and the `[DONE]` sentinel, strips the `data: ` prefix, and deserializes each remaining
+/// line into a [`OpenRouterStreamChunk`]. Reasoning deltas are wrapped in `<think>…</think>` as
+/// they arrive so callers can render them inline with content, unless `exclude_reasoning` is
+/// set, in which case they're dropped entirely.
+fn sse_content_stream(
+    bytes_stream: impl Stream<Item = reqwest::Result<bytes::Bytes>> + Send + 'static,
+    exclude_reasoning: bool,
+) -> BoxStream<'static, Result<String, OllamaError>> {
+    struct State<S> {
+        bytes_stream: S,
+        buffer: String,
+        done: bool,
+    }
+
+    stream::unfold(
+        State {
+            bytes_stream,
+            buffer: String::new(),
+            done: false,
+        },
+        |mut state| async move {
+            loop {
+                if state.done {
+                    return None;
+                }
+
+                if let Some(line_end) = state.buffer.find('\n') {
+                    let line = state.buffer[..line_end].trim_end_matches('\r').to_string();
+                    state.buffer.drain(..=line_end);
+
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let Some(data) = line.strip_prefix("data: ") else {
+                        continue;
+                    };
+                    if data == "[DONE]" {
+                        state.done = true;
+                        return None;
+                    }
+
+                    let chunk: OpenRouterStreamChunk = match serde_json::from_str(data) {
+                        Ok(chunk) => chunk,
+                        Err(e) => {
+                            state.done = true;
+                            return Some((
+                                Err(OllamaError::from(format!(
+                                    "Failed to parse stream chunk: {}",
                                     e
-                                )))
-                            }
+                                ))),
+                                state,
+                            ));
+                        }
+                    };
+                    let Some(choice) = chunk.choices.into_iter().next() else {
+                        continue;
+                    };
+                    if let Some(reasoning) = choice.delta.reasoning {
+                        if !exclude_reasoning {
+                            return Some((Ok(format!("<think>{}</think>", reasoning)), state));
                         }
+                        continue;
                     }
+                    if let Some(content) = choice.delta.content {
+                        return Some((Ok(content), state));
+                    }
+                    continue;
                 }
-            }
-        }
 
-        Ok(results.join("\n"))
-    }
+                match state.bytes_stream.next().await {
+                    Some(Ok(bytes)) => {
+                        state.buffer.push_str(&String::from_utf8_lossy(&bytes));
+                    }
+                    Some(Err(e)) => {
+                        state.done = true;
+                        return Some((
+                            Err(OllamaError::from(format!("Stream error: {}", e))),
+                            state,
+                        ));
+                    }
+                    None => {
+                        state.done = true;
+                        return None;
+                    }
+                }
+            }
+        },
+    )
+    .boxed()
 }
 
 #[cfg(test)]
@@ -333,12 +672,10 @@ mod tests {
 
         let executor = OpenRouterExecutor::new(model.to_string(), api_key);
 
+        let reasoning = model.has_reasoning().then(OpenRouterReasoning::default);
+
         let result = executor
-            .generate_text(
-                vec![MessageInput::new_user_message("Hi!")],
-                None,
-                model.has_reasoning(),
-            )
+            .generate_text(vec![MessageInput::new_user_message("Hi!")], None, reasoning)
             .await
             .unwrap();
 
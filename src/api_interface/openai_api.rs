@@ -1,4 +1,6 @@
-use crate::program::atomics::MessageInput;
+use crate::program::atomics::{MessageInput, ToolChoice};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt};
 use ollama_rs::{
     error::OllamaError, generation::functions::tools::Tool,
     generation::functions::OpenAIFunctionCall,
@@ -6,11 +8,53 @@ use ollama_rs::{
 use openai_dive::v1::api::Client;
 use openai_dive::v1::resources::chat::*;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+/// Maximum number of model round-trips `function_call` will make while feeding tool results
+/// back, before giving up without a final response.
+const DEFAULT_MAX_FUNCTION_CALL_STEPS: u32 = 5;
+
+/// Maximum number of tool calls `handle_normal_mode` runs at once, so a model requesting many
+/// calls in one turn doesn't overwhelm tool backends (e.g. the `browserless`/`scraper` tools).
+const DEFAULT_MAX_CONCURRENT_TOOL_CALLS: usize = 4;
+
+/// Maps the shared `ToolChoice` onto OpenAI's `tool_choice` request field: `Auto`/`None`/
+/// `Required` pass through as-is, `Force(name)` pins the named function via the
+/// `{"type": "function", "function": {"name": ...}}` shape the API expects.
+fn openai_tool_choice(tool_choice: &ToolChoice) -> ChatCompletionToolChoice {
+    match tool_choice {
+        ToolChoice::Auto => ChatCompletionToolChoice::Auto,
+        ToolChoice::None => ChatCompletionToolChoice::None,
+        ToolChoice::Required => ChatCompletionToolChoice::Required,
+        ToolChoice::Force(name) => {
+            ChatCompletionToolChoice::ChatCompletionNamedToolChoice(ChatCompletionNamedToolChoice {
+                r#type: ChatCompletionToolType::Function,
+                function: ChatCompletionFunctionName { name: name.clone() },
+            })
+        }
+    }
+}
+
+/// Tool-name prefix marking a tool as side-effecting (e.g. `execute_refund`,
+/// `execute_send_email`): `handle_normal_mode` runs calls to these through
+/// `OpenAIExecutor::confirmation_callback` before executing them. Read-only tools (the common
+/// case — no prefix) always run immediately.
+const CONFIRMATION_REQUIRED_PREFIX: &str = "execute_";
+
+fn requires_confirmation(tool_name: &str) -> bool {
+    tool_name.starts_with(CONFIRMATION_REQUIRED_PREFIX)
+}
 
 pub struct OpenAIExecutor {
     model: String,
     client: Client,
+    /// Gate for tools named with the `execute_` confirmation prefix: called with the tool's
+    /// name and parsed arguments before it runs, and the call is skipped (not aborted) if it
+    /// returns `false`. `None` (the default) runs every tool immediately, unchanged from before
+    /// this existed.
+    confirmation_callback: Option<Arc<dyn Fn(&str, &Value) -> bool + Send + Sync>>,
 }
 
 impl OpenAIExecutor {
@@ -18,9 +62,22 @@ impl OpenAIExecutor {
         Self {
             model,
             client: Client::new(api_key),
+            confirmation_callback: None,
         }
     }
 
+    /// Registers a callback gating tools named with the `execute_` confirmation prefix (see
+    /// [`requires_confirmation`]); `handle_normal_mode` calls it with the tool's name and parsed
+    /// arguments before running it, skipping the call if it returns `false`. Tools without the
+    /// prefix are unaffected.
+    pub fn with_confirmation_callback(
+        mut self,
+        callback: impl Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirmation_callback = Some(Arc::new(callback));
+        self
+    }
+
     pub async fn generate_text(
         &self,
         input: Vec<MessageInput>,
@@ -103,12 +160,101 @@ impl OpenAIExecutor {
         Ok(message)
     }
 
+    /// Streaming counterpart of [`Self::generate_text`]: requests the completion with `stream:
+    /// true` and yields each incremental text delta as it arrives, instead of blocking until
+    /// the full response is ready. Structured-output schemas aren't supported here, since
+    /// `generate_text`'s `strict` JSON-schema mode is a property of the complete response, not
+    /// something that can be validated delta by delta.
+    pub async fn generate_text_stream(
+        &self,
+        input: Vec<MessageInput>,
+    ) -> Result<BoxStream<'static, Result<String, OllamaError>>, OllamaError> {
+        let messages: Vec<ChatMessage> = input
+            .into_iter()
+            .map(|msg| match msg.role.as_str() {
+                "user" => ChatMessage::User {
+                    content: ChatMessageContent::Text(msg.content),
+                    name: None,
+                },
+                "assistant" => ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(msg.content)),
+                    tool_calls: None,
+                    name: None,
+                    refusal: None,
+                },
+                "system" => ChatMessage::System {
+                    content: ChatMessageContent::Text(msg.content),
+                    name: None,
+                },
+                _ => ChatMessage::User {
+                    content: ChatMessageContent::Text(msg.content),
+                    name: None,
+                },
+            })
+            .collect();
+
+        let parameters = ChatCompletionParametersBuilder::default()
+            .model(self.model.clone())
+            .messages(messages)
+            .stream(true)
+            .build()
+            .map_err(|e| {
+                OllamaError::from(format!("Could not build message parameters: {:?}", e))
+            })?;
+
+        let stream = self
+            .client
+            .chat()
+            .create_stream(parameters)
+            .await
+            .map_err(|e| OllamaError::from(format!("Failed to open stream: {:?}", e)))?;
+
+        Ok(stream::unfold(stream, |mut stream| async move {
+            loop {
+                let chunk = match stream.next().await? {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        return Some((
+                            Err(OllamaError::from(format!("Stream error: {:?}", e))),
+                            stream,
+                        ))
+                    }
+                };
+
+                let Some(content) = chunk
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                else {
+                    continue;
+                };
+
+                if content.is_empty() {
+                    continue;
+                }
+
+                return Some((Ok(content), stream));
+            }
+        })
+        .boxed())
+    }
+
+    /// Runs an agentic tool-calling loop: as long as the model keeps returning `tool_calls`,
+    /// each requested tool is executed and its result is fed back as a `ChatMessage::Tool`
+    /// message, then the full history is re-sent. Stops once the model responds with no
+    /// `tool_calls` (returning its final text) or after `DEFAULT_MAX_FUNCTION_CALL_STEPS`
+    /// round-trips, whichever comes first. `raw_mode` short-circuits the first step, returning
+    /// the requested calls verbatim instead of executing them. `tool_choice` pins the request's
+    /// `tool_choice` field (see [`openai_tool_choice`]) so a workflow step can require a
+    /// particular tool instead of leaving it to the model's discretion.
     pub async fn function_call(
         &self,
-        prompt: &str,
+        prompt: Vec<MessageInput>,
         tools: Vec<Arc<dyn Tool>>,
         raw_mode: bool,
         oai_parser: Arc<OpenAIFunctionCall>,
+        tool_choice: Option<&ToolChoice>,
     ) -> Result<String, OllamaError> {
         let openai_tools: Vec<_> = tools
             .iter()
@@ -122,30 +268,250 @@ impl OpenAIExecutor {
             })
             .collect();
 
-        let messages = vec![ChatMessage::User {
-            content: ChatMessageContent::Text(prompt.to_string()),
-            name: None,
-        }];
+        let request_tool_choice = tool_choice.map(openai_tool_choice);
+
+        // Carries the entire accumulated history (the original prompt plus any prior
+        // tool-call/tool-result turns), not just the most recent message, so a multi-step
+        // agentic loop (see `Executor::agentic_function_call`) actually re-queries with context
+        // instead of the model seeing only the latest "Tool results: ..." in isolation.
+        let mut messages: Vec<ChatMessage> = prompt
+            .into_iter()
+            .map(|msg| match msg.role.as_str() {
+                "assistant" => ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(msg.content)),
+                    tool_calls: None,
+                    name: None,
+                    refusal: None,
+                },
+                "system" => ChatMessage::System {
+                    content: ChatMessageContent::Text(msg.content),
+                    name: None,
+                },
+                _ => ChatMessage::User {
+                    content: ChatMessageContent::Text(msg.content),
+                    name: None,
+                },
+            })
+            .collect();
+
+        for _ in 0..DEFAULT_MAX_FUNCTION_CALL_STEPS {
+            let message = self
+                .send_function_call_request(
+                    &messages,
+                    openai_tools.clone(),
+                    request_tool_choice.clone(),
+                )
+                .await?;
+
+            let tool_calls = match &message {
+                ChatMessage::Assistant {
+                    tool_calls: Some(tool_calls),
+                    ..
+                } => tool_calls.clone(),
+                ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text)),
+                    ..
+                } => return Ok(text.clone()),
+                _ => {
+                    return Err(OllamaError::from(
+                        "Unexpected message content format".to_string(),
+                    ))
+                }
+            };
+
+            if raw_mode {
+                return self.handle_raw_mode(message);
+            }
+
+            messages.push(message);
+
+            let results = self
+                .handle_normal_mode(tool_calls, &tools, oai_parser.clone())
+                .await?;
+            for (tool_call_id, content) in results {
+                messages.push(ChatMessage::Tool {
+                    content: ChatMessageContent::Text(content),
+                    tool_call_id,
+                });
+            }
+        }
+
+        Err(OllamaError::from(format!(
+            "OpenAI: exceeded max_steps ({}) of agentic tool calling without a final response",
+            DEFAULT_MAX_FUNCTION_CALL_STEPS
+        )))
+    }
+
+    async fn send_function_call_request(
+        &self,
+        messages: &[ChatMessage],
+        openai_tools: Vec<ChatCompletionTool>,
+        tool_choice: Option<ChatCompletionToolChoice>,
+    ) -> Result<ChatMessage, OllamaError> {
+        let mut builder = ChatCompletionParametersBuilder::default();
+        builder
+            .model(self.model.clone())
+            .messages(messages.to_vec())
+            .tools(openai_tools);
+        if let Some(tool_choice) = tool_choice {
+            builder.tool_choice(tool_choice);
+        }
+        let parameters = builder.build().map_err(|e| {
+            OllamaError::from(format!("Could not build message parameters: {:?}", e))
+        })?;
+
+        let result = self.client.chat().create(parameters).await.map_err(|e| {
+            OllamaError::from(format!("Failed to parse OpenAI API response: {:?}", e))
+        })?;
+
+        result
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| OllamaError::from("No response generated".to_string()))
+    }
+
+    /// Streaming counterpart of [`Self::function_call`]'s first round-trip: requests a
+    /// streamed completion and reconstructs `tool_calls` from their per-index deltas (OpenAI
+    /// streams a tool call's `function.name` once and its `function.arguments` in fragments,
+    /// tagged by the call's position in the response) while forwarding any interleaved text
+    /// content to the returned stream as it arrives. Once the model's turn ends, every
+    /// reconstructed call is dispatched through the same tool-execution path `handle_normal_mode`
+    /// uses, in call order, and the joined results are yielded as one final stream item. Unlike
+    /// `function_call`, this covers a single round-trip only; it doesn't loop further turns back
+    /// through the model.
+    pub async fn function_call_stream(
+        &self,
+        prompt: &str,
+        tools: Vec<Arc<dyn Tool>>,
+        oai_parser: Arc<OpenAIFunctionCall>,
+    ) -> Result<BoxStream<'static, Result<String, OllamaError>>, OllamaError> {
+        let openai_tools: Vec<_> = tools
+            .iter()
+            .map(|tool| ChatCompletionTool {
+                r#type: ChatCompletionToolType::Function,
+                function: ChatCompletionFunction {
+                    name: tool.name().to_lowercase().replace(' ', "_"),
+                    description: Some(tool.description()),
+                    parameters: tool.parameters(),
+                },
+            })
+            .collect();
 
         let parameters = ChatCompletionParametersBuilder::default()
             .model(self.model.clone())
-            .messages(messages)
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::Text(prompt.to_string()),
+                name: None,
+            }])
             .tools(openai_tools)
+            .stream(true)
             .build()
             .map_err(|e| {
                 OllamaError::from(format!("Could not build message parameters: {:?}", e))
             })?;
 
-        let result = self.client.chat().create(parameters).await.map_err(|e| {
-            OllamaError::from(format!("Failed to parse OpenAI API response: {:?}", e))
-        })?;
-        let message = result.choices[0].message.clone();
+        let mut stream = self
+            .client
+            .chat()
+            .create_stream(parameters)
+            .await
+            .map_err(|e| OllamaError::from(format!("Failed to open stream: {:?}", e)))?;
 
-        if raw_mode {
-            self.handle_raw_mode(message)
-        } else {
-            self.handle_normal_mode(message, tools, oai_parser).await
-        }
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut calls: HashMap<_, (String, String)> = HashMap::new();
+
+            while let Some(chunk) = stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(OllamaError::from(format!("Stream error: {:?}", e))));
+                        return;
+                    }
+                };
+
+                let Some(choice) = chunk.choices.into_iter().next() else {
+                    continue;
+                };
+
+                if let Some(deltas) = choice.delta.tool_calls {
+                    for delta in deltas {
+                        let entry = calls.entry(delta.index).or_default();
+                        if let Some(function) = delta.function {
+                            if let Some(name) = function.name {
+                                entry.0 = name;
+                            }
+                            if let Some(arguments) = function.arguments {
+                                entry.1.push_str(&arguments);
+                            }
+                        }
+                    }
+                }
+
+                if let Some(content) = choice.delta.content {
+                    if !content.is_empty() && tx.send(Ok(content)).is_err() {
+                        return;
+                    }
+                }
+            }
+
+            if calls.is_empty() {
+                return;
+            }
+
+            let mut indices: Vec<_> = calls.keys().copied().collect();
+            indices.sort_unstable();
+
+            let mut results = Vec::with_capacity(indices.len());
+            for index in indices {
+                let (name, arguments) = calls.remove(&index).unwrap();
+                let Some(tool) = tools
+                    .iter()
+                    .find(|tool| tool.name().to_lowercase().replace(' ', "_") == name)
+                else {
+                    let _ = tx.send(Err(OllamaError::from(format!(
+                        "OpenAI: no matching tool found for function: {}",
+                        name
+                    ))));
+                    return;
+                };
+
+                let tool_params: Value = match serde_json::from_str(&arguments) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        let _ = tx.send(Err(OllamaError::from(format!(
+                            "Could not parse tool arguments: {:?}",
+                            e
+                        ))));
+                        return;
+                    }
+                };
+
+                match oai_parser
+                    .function_call_with_history(name, tool_params, tool.clone())
+                    .await
+                {
+                    Ok(result) => results.push(result.message.unwrap().content),
+                    Err(e) => {
+                        let _ = tx.send(Err(OllamaError::from(format!(
+                            "OpenAI: could not generate text: {:?}",
+                            e
+                        ))));
+                        return;
+                    }
+                }
+            }
+
+            let _ = tx.send(Ok(results.join("\n")));
+        });
+
+        Ok(stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        })
+        .boxed())
     }
 
     fn handle_raw_mode(&self, message: ChatMessage) -> Result<String, OllamaError> {
@@ -168,45 +534,158 @@ impl OpenAIExecutor {
         Ok(raw_calls.join("\n\n"))
     }
 
+    /// Executes every requested tool call concurrently (bounded by
+    /// `DEFAULT_MAX_CONCURRENT_TOOL_CALLS` in-flight at once) and returns each result paired
+    /// with the `tool_call.id` it answers, in the original call order, so the caller can feed
+    /// them back to the model as `ChatMessage::Tool` messages. A model emitting several
+    /// parallel tool calls (e.g. scraping two URLs) pays the slowest single call's latency
+    /// instead of their sum. Any single tool error aborts the whole batch, except a denied
+    /// confirmation (see [`requires_confirmation`]), which only skips that one call.
     async fn handle_normal_mode(
         &self,
-        message: ChatMessage,
-        tools: Vec<Arc<dyn Tool>>,
+        tool_calls: Vec<ToolCall>,
+        tools: &[Arc<dyn Tool>],
         oai_parser: Arc<OpenAIFunctionCall>,
-    ) -> Result<String, OllamaError> {
-        let mut results = Vec::<String>::new();
+    ) -> Result<Vec<(String, String)>, OllamaError> {
+        let mut calls = Vec::with_capacity(tool_calls.len());
 
-        if let ChatMessage::Assistant {
-            tool_calls: Some(tool_calls),
-            ..
-        } = message
-        {
-            for tool_call in tool_calls {
-                for tool in &tools {
-                    if tool.name().to_lowercase().replace(' ', "_") == tool_call.function.name {
-                        let tool_params: Value =
-                            serde_json::from_str(&tool_call.function.arguments)?;
-                        let res = oai_parser
-                            .function_call_with_history(
-                                tool_call.function.name.clone(),
-                                tool_params,
-                                tool.clone(),
-                            )
-                            .await;
-                        match res {
-                            Ok(result) => results.push(result.message.unwrap().content),
-                            Err(e) => {
-                                return Err(OllamaError::from(format!(
-                                    "Could not generate text: {:?}",
-                                    e
-                                )))
-                            }
-                        }
+        for tool_call in tool_calls {
+            let matched_tool = tools.iter().find(|tool| {
+                tool.name().to_lowercase().replace(' ', "_") == tool_call.function.name
+            });
+
+            let Some(tool) = matched_tool else {
+                return Err(OllamaError::from(format!(
+                    "OpenAI: no matching tool found for function: {}",
+                    tool_call.function.name
+                )));
+            };
+
+            let tool_params: Value = serde_json::from_str(&tool_call.function.arguments)?;
+            calls.push((
+                tool_call.id,
+                tool_call.function.name,
+                tool_params,
+                tool.clone(),
+            ));
+        }
+
+        // Side-effecting tools (named with the `execute_` confirmation prefix) are gated behind
+        // `confirmation_callback`; a denial is fed back to the model as the call's result
+        // instead of aborting the rest of the batch. Read-only tools, and any tool when no
+        // callback is registered, run unconditionally.
+        let mut results: Vec<Option<(String, String)>> = vec![None; calls.len()];
+        let mut pending = Vec::new();
+        for (i, (tool_call_id, function_name, tool_params, tool)) in calls.into_iter().enumerate() {
+            let confirmed = !requires_confirmation(&function_name)
+                || self
+                    .confirmation_callback
+                    .as_ref()
+                    .map(|callback| callback(&function_name, &tool_params))
+                    .unwrap_or(true);
+
+            if confirmed {
+                pending.push((i, tool_call_id, function_name, tool_params, tool));
+            } else {
+                results[i] = Some((
+                    tool_call_id,
+                    format!(
+                        "Tool `{}` was not confirmed by the user; call skipped",
+                        function_name
+                    ),
+                ));
+            }
+        }
+
+        let semaphore = Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_TOOL_CALLS));
+        let run_results = future::try_join_all(pending.iter().map(
+            |(_, tool_call_id, function_name, tool_params, tool)| {
+                let tool_call_id = tool_call_id.clone();
+                let function_name = function_name.clone();
+                let tool_params = tool_params.clone();
+                let tool = tool.clone();
+                let oai_parser = oai_parser.clone();
+                let semaphore = semaphore.clone();
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    let res = oai_parser
+                        .function_call_with_history(function_name, tool_params, tool)
+                        .await;
+                    match res {
+                        Ok(result) => Ok((tool_call_id, result.message.unwrap().content)),
+                        Err(e) => Err(OllamaError::from(format!(
+                            "OpenAI: could not generate text: {:?}",
+                            e
+                        ))),
                     }
                 }
+            },
+        ))
+        .await?;
+
+        for ((i, ..), result) in pending.into_iter().zip(run_results) {
+            results[i] = Some(result);
+        }
+
+        Ok(results
+            .into_iter()
+            .map(|result| result.expect("every call was either confirmed-denied or just run"))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_confirmation_matches_only_the_execute_prefix() {
+        assert!(requires_confirmation("execute_refund"));
+        assert!(requires_confirmation("execute_send_email"));
+        assert!(!requires_confirmation("scraper"));
+        assert!(!requires_confirmation("weather"));
+    }
+
+    #[test]
+    fn openai_tool_choice_maps_auto_none_and_required_through() {
+        assert!(matches!(
+            openai_tool_choice(&ToolChoice::Auto),
+            ChatCompletionToolChoice::Auto
+        ));
+        assert!(matches!(
+            openai_tool_choice(&ToolChoice::None),
+            ChatCompletionToolChoice::None
+        ));
+        assert!(matches!(
+            openai_tool_choice(&ToolChoice::Required),
+            ChatCompletionToolChoice::Required
+        ));
+    }
+
+    #[test]
+    fn openai_tool_choice_forces_the_named_function() {
+        let choice = openai_tool_choice(&ToolChoice::Force("scraper".to_string()));
+        match choice {
+            ChatCompletionToolChoice::ChatCompletionNamedToolChoice(named) => {
+                assert_eq!(named.function.name, "scraper");
             }
+            _ => panic!("expected a named tool choice"),
         }
+    }
+
+    #[test]
+    fn with_confirmation_callback_is_invoked_and_stored() {
+        let executor = OpenAIExecutor::new("gpt-4o".to_string(), "key".to_string())
+            .with_confirmation_callback(|name, _args| name == "execute_refund");
 
-        Ok(results.join("\n"))
+        let callback = executor
+            .confirmation_callback
+            .as_ref()
+            .expect("callback should be set");
+        assert!(callback("execute_refund", &Value::Null));
+        assert!(!callback("execute_delete", &Value::Null));
     }
 }
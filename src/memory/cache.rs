@@ -1,17 +1,94 @@
 use super::types::{Entry, ID};
+use crate::program::atomics::MessageInput;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Computes a stable SHA-256 digest for a memoizable LLM request: the rendered prompt
+/// messages, model name, generation parameters, and optional structured-output schema. Two
+/// requests that would produce the same call to the provider hash to the same digest.
+pub fn digest_request(
+    messages: &[MessageInput],
+    model: &str,
+    temperature: Option<f64>,
+    top_k: Option<i32>,
+    max_tokens: Option<i32>,
+    schema: Option<&str>,
+) -> String {
+    let mut hasher = Sha256::new();
+    for message in messages {
+        hasher.update(message.role.as_bytes());
+        hasher.update([0u8]); // separator so role/content can't collide across messages
+        hasher.update(message.content.as_bytes());
+    }
+    hasher.update(model.as_bytes());
+    hasher.update(format!("{:?}|{:?}|{:?}", temperature, top_k, max_tokens).as_bytes());
+    if let Some(schema) = schema {
+        hasher.update(schema.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Computes a stable SHA-256 digest for a `(tool_name, arguments)` pair, so an identical tool
+/// call made again later in the same workflow can reuse the previous result via
+/// `ProgramMemory::get_memoized`/`set_memoized` instead of re-running the tool. `name` is
+/// expected already lowercased (every caller matches tools by their
+/// `name().to_lowercase().replace(' ', "_")` form), and `arguments`'s `Value::to_string()` gives
+/// a canonical serialization regardless of the original key order in the model's JSON.
+pub fn digest_tool_call(name: &str, arguments: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"tool_call");
+    hasher.update([0u8]);
+    hasher.update(name.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(arguments.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PersistedCache {
+    results: HashMap<String, Entry>,
+}
 
 pub struct Cache {
     pages: HashMap<ID, Entry>,
+    /// Content-addressed memoization of LLM task outputs, keyed by `digest_request`.
+    results: HashMap<String, Entry>,
+    /// When set, `set_memoized` flushes `results` to this path after every write.
+    persist_path: Option<PathBuf>,
 }
 
 impl Cache {
     pub fn new() -> Self {
         Cache {
             pages: HashMap::new(),
+            results: HashMap::new(),
+            persist_path: None,
         }
     }
 
+    /// Opens (or creates) a persistent memoization store at `path`, loading any digest ->
+    /// output pairs left over from a previous run.
+    pub fn with_persistent_cache(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let results = if path.exists() {
+            let raw = fs::read_to_string(&path)?;
+            serde_json::from_str::<PersistedCache>(&raw)
+                .unwrap_or_default()
+                .results
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Cache {
+            pages: HashMap::new(),
+            results,
+            persist_path: Some(path),
+        })
+    }
+
     pub fn get(&self, key: &ID) -> Option<Entry> {
         self.pages.get(key).cloned()
     }
@@ -19,4 +96,23 @@ impl Cache {
     pub fn set(&mut self, key: ID, value: Entry) {
         self.pages.insert(key, value);
     }
+
+    /// Reads a memoized LLM output for `digest`, if one was previously stored.
+    pub fn get_memoized(&self, digest: &str) -> Option<Entry> {
+        self.results.get(digest).cloned()
+    }
+
+    /// Stores a memoized LLM output for `digest`, flushing to disk if a persistent path is set.
+    pub fn set_memoized(&mut self, digest: String, value: Entry) {
+        self.results.insert(digest, value);
+
+        if let Some(path) = &self.persist_path {
+            let persisted = PersistedCache {
+                results: self.results.clone(),
+            };
+            if let Ok(raw) = serde_json::to_string(&persisted) {
+                let _ = fs::write(path, raw);
+            }
+        }
+    }
 }
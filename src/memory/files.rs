@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
-use super::types::{Entry, FilePage};
+use super::types::{ChunkSource, Entry, FilePage, QuantizedEmbedding};
 use crate::program::errors::{EmbeddingError, FileSystemError};
 use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
 use log::debug;
 use ollama_rs::generation::embeddings::request::{EmbeddingsInput, GenerateEmbeddingsRequest};
 use ollama_rs::Ollama;
@@ -13,9 +16,10 @@ use openai_dive::v1::resources::embedding::{
 };
 use serde_json::json;
 #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
-use simsimd::SpatialSimilarity;
+use simsimd::{BinarySimilarity, SpatialSimilarity};
 
-use text_splitter::TextSplitter;
+use text_splitter::{CodeSplitter, TextSplitter};
+use usearch::{new_index, Index, IndexOptions, MetricKind, ScalarKind};
 
 #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
 mod arm_compat {
@@ -50,10 +54,61 @@ use self::arm_compat::SimpleSpatialSimilarity;
 
 pub static EMBEDDING_MODEL: &str = "hellord/mxbai-embed-large-v1:f16";
 
+/// Which ranking signal(s) `FileSystem::search_with_mode` combines when ranking stored entries
+/// against a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Pure embedding cosine similarity — `search`'s original behavior.
+    Semantic,
+    /// Pure lexical BM25 ranking over tokenized entries.
+    Keyword,
+    /// Reciprocal Rank Fusion of the semantic and keyword rankings.
+    Hybrid,
+}
+
+/// How `FileSystem` stores each entry's embedding for its first-pass candidate scan. `Int8`/
+/// `Binary` entries trade ranking precision for a smaller memory footprint and a cheaper scan;
+/// the winners of that coarse pass are always rescaled against the full-precision embedding
+/// before final results are returned (see `FileSystem::rerank_top`), so quantization only
+/// affects which candidates survive to the rerank, not the score they're ultimately ranked by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuantizationMode {
+    /// Scan full-precision `Vec<f32>` embeddings directly — `FileSystem::new`'s default.
+    #[default]
+    Full,
+    /// Per-component int8 scalar quantization (see `QuantizedEmbedding::Int8`).
+    Int8,
+    /// One sign bit per dimension, compared via Hamming distance (see `QuantizedEmbedding::Binary`).
+    Binary,
+}
+
+/// This is the `Embedder` abstraction `FileSystem` actually uses. The crate also carries
+/// `api_interface::embeddings::RestEmbedder` (used by `Executor::generate_embeddings`, which
+/// has no notion of `FileSystem`'s stored entries) as a second, independently-grown embedding
+/// abstraction. A third, `memory::semantic::Embedder`, duplicated this one behind a `SemanticCache`
+/// that depended on an orphaned `core`-era module tree never declared from `lib.rs`; since
+/// everything it provided (pluggable embedders, ANN search, hybrid ranking) already existed here
+/// and was reachable, it was removed rather than wired in. Prefer this trait for anything
+/// touching `ProgramMemory`/`FileSystem`; don't add a third.
 #[async_trait]
 pub trait Embedder: Send + Sync {
     async fn generate_embeddings(&self, prompt: &str) -> Result<Vec<f32>, EmbeddingError>;
     async fn generate_query_embeddings(&self, query: &str) -> Result<Vec<f32>, EmbeddingError>;
+
+    /// Embeds `texts` in as few round-trips as the backend allows. The default fans requests
+    /// out through `generate_embeddings` with bounded concurrency; implementations whose
+    /// backend accepts a single batched request (see `OllamaEmbedder`, `OpenAIEmbedder`)
+    /// override this to submit them all at once instead.
+    async fn embed_chunks(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        const CONCURRENCY: usize = 8;
+        stream::iter(texts)
+            .map(|text| self.generate_embeddings(text))
+            .buffered(CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
 }
 
 struct OllamaEmbedder {}
@@ -90,6 +145,23 @@ impl Embedder for OllamaEmbedder {
             Err(_) => Err(EmbeddingError::QueryEmbedding(query.to_string())),
         }
     }
+
+    async fn embed_chunks(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let ollama = Ollama::default();
+        let res = ollama
+            .generate_embeddings(GenerateEmbeddingsRequest::new(
+                EMBEDDING_MODEL.to_string(),
+                EmbeddingsInput::Multiple(texts.to_vec()),
+            ))
+            .await;
+        match res {
+            Ok(res) => Ok(res.embeddings),
+            Err(_) => Err(EmbeddingError::DocumentEmbedding(format!(
+                "batch of {} chunks",
+                texts.len()
+            ))),
+        }
+    }
 }
 
 struct OpenAIEmbedder {}
@@ -131,28 +203,208 @@ impl Embedder for OpenAIEmbedder {
     async fn generate_query_embeddings(&self, _query: &str) -> Result<Vec<f32>, EmbeddingError> {
         self.generate_embeddings(_query).await
     }
+
+    async fn embed_chunks(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let api_key = std::env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
+        let client = Client::new(api_key);
+
+        let parameters = EmbeddingParametersBuilder::default()
+            .model(EmbeddingsEngine::TextEmbeddingAda002.to_string())
+            .input(EmbeddingInput::StringArray(texts.to_vec()))
+            .encoding_format(EmbeddingEncodingFormat::Float)
+            .build()
+            .map_err(EmbeddingError::BuilderError)?;
+
+        let result = client.embeddings().create(parameters).await;
+
+        match result {
+            Ok(result) => {
+                let mut data = result.data;
+                data.sort_by_key(|d| d.index);
+                data.into_iter()
+                    .map(|d| match d.embedding {
+                        EmbeddingOutput::Float(f64_vec) => {
+                            Ok(f64_vec.iter().map(|&x| x as f32).collect())
+                        }
+                        _ => Err(EmbeddingError::DocumentEmbedding(
+                            "OpenAI embedding result conversion error".to_string(),
+                        )),
+                    })
+                    .collect()
+            }
+            Err(_) => Err(EmbeddingError::DocumentEmbedding(
+                "OpenAI Embedding batch response error".to_string(),
+            )),
+        }
+    }
+}
+
+/// Embeds against any REST endpoint that accepts a JSON body and returns the embedding array
+/// somewhere in its JSON response, driven entirely by environment configuration rather than a
+/// hardcoded request/response shape. Lets callers point the file system at an OpenAI-compatible
+/// or custom embedding server (HuggingFace TEI, vLLM, a self-hosted gateway) without a crate
+/// change. `EMBEDDING_REST_REQUEST_TEMPLATE` is a JSON object with a `"{{text}}"` placeholder
+/// somewhere inside it (written into the final request in place of the text being embedded);
+/// `EMBEDDING_REST_OUTPUT_PATH` is a JSON pointer (e.g. `/data/0/embedding`) locating the
+/// embedding array in the response.
+struct RestEmbedder {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    request_template: Value,
+    output_field_path: String,
+}
+
+impl RestEmbedder {
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("EMBEDDING_REST_URL").ok()?;
+        let request_template: Value = std::env::var("EMBEDDING_REST_REQUEST_TEMPLATE")
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_else(|| json!({ "input": "{{text}}" }));
+        let output_field_path = std::env::var("EMBEDDING_REST_OUTPUT_PATH")
+            .unwrap_or_else(|_| "/data/0/embedding".to_string());
+        let headers = std::env::var("EMBEDDING_REST_HEADERS")
+            .ok()
+            .and_then(|raw| serde_json::from_str::<HashMap<String, String>>(&raw).ok())
+            .map(|map| map.into_iter().collect())
+            .unwrap_or_default();
+
+        Some(Self {
+            client: reqwest::Client::new(),
+            url,
+            headers,
+            request_template,
+            output_field_path,
+        })
+    }
+
+    /// Substitutes every occurrence of `{{text}}` in any string leaf of `value` with `text`.
+    fn fill_template(value: &Value, text: &str) -> Value {
+        match value {
+            Value::String(s) => Value::String(s.replace("{{text}}", text)),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| Self::fill_template(item, text))
+                    .collect(),
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), Self::fill_template(v, text)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    async fn request(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let body = Self::fill_template(&self.request_template, text);
+
+        let mut request = self.client.post(&self.url).json(&body);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|_| EmbeddingError::DocumentEmbedding(text.to_string()))?
+            .error_for_status()
+            .map_err(|_| EmbeddingError::DocumentEmbedding(text.to_string()))?;
+
+        let parsed: Value = response
+            .json()
+            .await
+            .map_err(|_| EmbeddingError::DocumentEmbedding(text.to_string()))?;
+
+        parsed
+            .pointer(&self.output_field_path)
+            .and_then(Value::as_array)
+            .map(|arr| {
+                arr.iter()
+                    .map(|v| v.as_f64().unwrap_or_default() as f32)
+                    .collect()
+            })
+            .ok_or_else(|| EmbeddingError::DocumentEmbedding(text.to_string()))
+    }
+}
+
+#[async_trait]
+impl Embedder for RestEmbedder {
+    async fn generate_embeddings(&self, prompt: &str) -> Result<Vec<f32>, EmbeddingError> {
+        self.request(prompt).await
+    }
+
+    async fn generate_query_embeddings(&self, query: &str) -> Result<Vec<f32>, EmbeddingError> {
+        let prompt = OllamaEmbedder::transform_query(query);
+        self.request(&prompt)
+            .await
+            .map_err(|_| EmbeddingError::QueryEmbedding(query.to_string()))
+    }
 }
 
 pub struct FileSystem {
     embedder: Arc<dyn Embedder>,
     entries: Vec<FilePage>,
+    /// Approximate nearest-neighbor index over `entries`' embeddings, keyed by each entry's
+    /// index in `entries` so no separate id mapping is needed. Built lazily on the first `add`
+    /// (once an embedding's dimensionality is known) and consulted by `search_with_mode`/
+    /// `have_similar` once `entries` is large enough that a brute-force scan would be wasteful;
+    /// see `ann_search`.
+    index: Option<Index>,
+    /// How new entries' embeddings are stored for `brute_force_top_n`'s first-pass scan; see
+    /// `QuantizationMode`. Set at construction via `with_quantization`; `new` defaults to `Full`.
+    quantization: QuantizationMode,
 }
 
 impl FileSystem {
     pub fn new() -> Self {
-        if std::env::var("OPENAI_API_KEY").is_ok() {
+        if let Some(embedder) = RestEmbedder::from_env() {
+            FileSystem {
+                embedder: Arc::new(embedder),
+                entries: Vec::new(),
+                index: None,
+                quantization: QuantizationMode::default(),
+            }
+        } else if std::env::var("OPENAI_API_KEY").is_ok() {
             FileSystem {
                 embedder: Arc::new(OpenAIEmbedder {}),
                 entries: Vec::new(),
+                index: None,
+                quantization: QuantizationMode::default(),
             }
         } else {
             FileSystem {
                 embedder: Arc::new(OllamaEmbedder {}),
                 entries: Vec::new(),
+                index: None,
+                quantization: QuantizationMode::default(),
             }
         }
     }
 
+    /// Like `new`, but stores every subsequently added entry's embedding in the given
+    /// `quantization` mode instead of full precision. See `QuantizationMode`.
+    pub fn with_quantization(quantization: QuantizationMode) -> Self {
+        FileSystem {
+            quantization,
+            ..Self::new()
+        }
+    }
+
+    /// Computes `embedding`'s compact representation for the current `quantization` mode, or
+    /// `None` under `QuantizationMode::Full`. Always quantizes the unit-normalized embedding
+    /// rather than the raw one, so every stored and query vector shares the same component
+    /// range and `quantized_similarity` can compare them directly (see `quantize_int8`).
+    fn quantize(&self, embedding: &[f32]) -> Option<QuantizedEmbedding> {
+        match self.quantization {
+            QuantizationMode::Full => None,
+            QuantizationMode::Int8 => Some(quantize_int8(&normalize(embedding))),
+            QuantizationMode::Binary => Some(quantize_binary(embedding)),
+        }
+    }
+
     pub async fn add(&mut self, entry: &Entry) -> Result<(), FileSystemError> {
         let doc = match entry {
             Entry::String(s) => s,
@@ -160,50 +412,96 @@ impl FileSystem {
         };
 
         let splitter = TextSplitter::new(250);
-        let chunks = splitter.chunks(doc);
-        let sentences: Vec<String> = chunks.map(|s| s.to_string()).collect();
+        let sentences: Vec<String> = splitter
+            .chunks(doc)
+            .map(|s| s.to_string())
+            .filter(|s| s.len() >= 25)
+            .collect();
 
-        for sentence in sentences {
-            if sentence.len() < 25 {
-                continue;
-            }
-            let embedding = self.embedder.generate_embeddings(&sentence).await;
-            match embedding {
-                Ok(embedding) => {
-                    //convert to f32
-                    self.entries.push((sentence.to_string(), embedding));
-                }
-                Err(err) => return Err(FileSystemError::EmbeddingError(err)),
-            }
+        if sentences.is_empty() {
+            return Ok(());
+        }
+
+        // One batched (or bounded-concurrency) round-trip for every eligible chunk, instead of
+        // awaiting `generate_embeddings` one sentence at a time.
+        let embeddings = self
+            .embedder
+            .embed_chunks(&sentences)
+            .await
+            .map_err(FileSystemError::EmbeddingError)?;
+
+        for (sentence, embedding) in sentences.into_iter().zip(embeddings) {
+            // The index is scale-sensitive (it compares via inner product), so it gets its own
+            // unit-normalized copy; `entries` keeps the raw embedding, since `cosine`/
+            // `bm25_scores` don't need it pre-normalized.
+            let unit = normalize(&embedding);
+            let index = self
+                .index
+                .get_or_insert_with(|| new_index_with_dimensions(unit.len()));
+            let id = self.entries.len() as u64;
+            // Best-effort: entries still get indexed for brute-force/BM25 even if the ANN
+            // index insertion fails for some reason.
+            let _ = index.add(id, &unit);
+            let quantized = self.quantize(&embedding);
+            self.entries.push(FilePage {
+                text: sentence,
+                embedding,
+                source: None,
+                quantized,
+            });
         }
 
         Ok(())
     }
 
-    pub async fn search(&self, query: &Entry) -> Result<Vec<Entry>, FileSystemError> {
-        let query_embedding = self
+    /// Like `add`, but splits `text` with `chunk_document` — syntax-aware for a known `language`,
+    /// falling back to paragraph/sentence splitting, with `overlap_tokens` of trailing context
+    /// carried across chunk boundaries — and records each chunk's `source_id` and byte range so
+    /// `search_with_mode` can point a result back to the document and span it came from.
+    pub async fn add_document(
+        &mut self,
+        source_id: &str,
+        text: &str,
+        language: SourceLanguage,
+        max_tokens: usize,
+        overlap_tokens: usize,
+    ) -> Result<(), FileSystemError> {
+        let chunks = chunk_document(text, &language, max_tokens, overlap_tokens);
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let texts: Vec<String> = chunks.iter().map(|(chunk, _)| chunk.clone()).collect();
+        let embeddings = self
             .embedder
-            .generate_query_embeddings(&query.to_string())
-            .await;
-        match query_embedding {
-            Ok(embedding) => {
-                //to f32
-                let res = self.brute_force_top_n(&embedding, 3);
-
-                let mut passages = Vec::new();
-                for r in res {
-                    //can add distance threshold here
-                    debug!("Similarity: {}, passage: {}", r.1, r.0);
-                    let entry = Entry::Json(json!({
-                        "passage": r.0,
-                        "similarity": r.1
-                    }));
-                    passages.push(entry);
-                }
-                Ok(passages)
-            }
-            Err(err) => Err(FileSystemError::EmbeddingError(err)),
+            .embed_chunks(&texts)
+            .await
+            .map_err(FileSystemError::EmbeddingError)?;
+
+        for ((chunk, range), embedding) in chunks.into_iter().zip(embeddings) {
+            let unit = normalize(&embedding);
+            let index = self
+                .index
+                .get_or_insert_with(|| new_index_with_dimensions(unit.len()));
+            let id = self.entries.len() as u64;
+            let _ = index.add(id, &unit);
+            let quantized = self.quantize(&embedding);
+            self.entries.push(FilePage {
+                text: chunk,
+                embedding,
+                source: Some(ChunkSource {
+                    source_id: source_id.to_string(),
+                    range,
+                }),
+                quantized,
+            });
         }
+
+        Ok(())
+    }
+
+    pub async fn search(&self, query: &Entry) -> Result<Vec<Entry>, FileSystemError> {
+        self.search_with_mode(query, SearchMode::Semantic, 3).await
     }
 
     pub async fn have_similar(
@@ -223,38 +521,551 @@ impl FileSystem {
 
         match query_embedding {
             Ok(embedding) => {
-                let res = self.brute_force_top_n(&embedding, 1);
-
-                let sim = res[0].1;
-                if sim > thres {
-                    return Ok(true);
-                }
-                Ok(false)
+                let sim = match self.ann_search(&embedding, 1) {
+                    Some(hits) => hits.first().map(|&(_, sim)| sim).unwrap_or(0.0),
+                    None => self
+                        .brute_force_top_n(&embedding, 1)
+                        .first()
+                        .map(|(_, sim)| *sim)
+                        .unwrap_or(0.0),
+                };
+                Ok(sim > thres)
             }
             Err(err) => Err(FileSystemError::EmbeddingError(err)),
         }
     }
 
+    /// Embeds `query` and `candidate` independently and returns their cosine similarity, for
+    /// callers (e.g. the `Search` operator's hybrid mode) that want to rank arbitrary text
+    /// against a query without first inserting it into `entries`.
+    pub async fn similarity(&self, query: &str, candidate: &str) -> Result<f32, FileSystemError> {
+        let query_embedding = self
+            .embedder
+            .generate_query_embeddings(query)
+            .await
+            .map_err(FileSystemError::EmbeddingError)?;
+        let candidate_embedding = self
+            .embedder
+            .generate_embeddings(candidate)
+            .await
+            .map_err(FileSystemError::EmbeddingError)?;
+
+        Ok(cosine(&query_embedding, &candidate_embedding))
+    }
+
     fn brute_force_top_n(&self, query: &[f32], n: usize) -> Vec<(String, f32)> {
-        let mut similarities = Vec::new();
-        for (_, v) in &self.entries {
-            #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
-            let similarity = f32::cosine(query, v).unwrap_or(0.0) as f32;
+        let coarse = self.scored_candidates(query);
+        self.rerank_top(query, &coarse, n)
+            .into_iter()
+            .map(|(i, score)| (self.entries[i].text.clone(), score))
+            .collect()
+    }
 
-            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
-            let similarity = f32::cosine(query, v).unwrap_or(0.0);
+    /// Below this many coarse candidates, reranking all of them at full precision is already
+    /// cheap enough that there's no point narrowing further; above it, only the top
+    /// `RERANK_POOL` coarse candidates get rescored. Irrelevant when `quantization` is `Full`,
+    /// since `scored_candidates` already returns the exact cosine score.
+    const RERANK_POOL: usize = 50;
 
-            similarities.push(similarity);
+    /// Per-entry similarity estimate against `query`: exact cosine when `quantization` is
+    /// `Full`, or a comparison against each entry's compact `quantized` representation
+    /// otherwise — `rerank_top` always rescales the coarse winners before returning final
+    /// results, so this only needs to rank candidates roughly, not score them exactly.
+    fn scored_candidates(&self, query: &[f32]) -> Vec<f32> {
+        match self.quantization {
+            QuantizationMode::Full => self
+                .entries
+                .iter()
+                .map(|p| cosine(query, &p.embedding))
+                .collect(),
+            QuantizationMode::Int8 | QuantizationMode::Binary => {
+                let query_quantized = self.quantize(query);
+                self.entries
+                    .iter()
+                    .map(|p| match (&query_quantized, &p.quantized) {
+                        (Some(q), Some(e)) => quantized_similarity(q, e),
+                        _ => cosine(query, &p.embedding),
+                    })
+                    .collect()
+            }
         }
+    }
 
-        let mut indices: Vec<usize> = (0..similarities.len()).collect();
-        indices.sort_by(|&a, &b| similarities[b].partial_cmp(&similarities[a]).unwrap());
-        let top_indices: Vec<usize> = indices.into_iter().take(n).collect();
+    /// Takes the `n.max(RERANK_POOL)` best-ranked `coarse` candidates and rescores them against
+    /// `query` at full embedding precision, returning the top `n` by that exact score.
+    fn rerank_top(&self, query: &[f32], coarse: &[f32], n: usize) -> Vec<(usize, f32)> {
+        let pool = n.max(Self::RERANK_POOL);
+        let mut reranked: Vec<(usize, f32)> = rank_by(coarse)
+            .into_iter()
+            .take(pool)
+            .map(|i| (i, cosine(query, &self.entries[i].embedding)))
+            .collect();
+        reranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        reranked.truncate(n);
+        reranked
+    }
+
+    /// Tokenizes `entries` and scores them against `query` with Okapi BM25
+    /// (`k1 = 1.2`, `b = 0.75`), for `search_with_mode`'s keyword/hybrid ranking.
+    fn bm25_scores(&self, query: &str) -> Vec<f32> {
+        const K1: f32 = 1.2;
+        const B: f32 = 0.75;
 
-        let top_results: Vec<(String, f32)> = top_indices
+        let docs: Vec<Vec<String>> = self.entries.iter().map(|p| tokenize(&p.text)).collect();
+        let n = docs.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let avgdl = docs.iter().map(|d| d.len()).sum::<usize>() as f32 / n as f32;
+        let query_terms = tokenize(query);
+
+        let doc_freq: HashMap<&str, usize> = query_terms
             .iter()
-            .map(|&i| (self.entries[i].0.clone(), similarities[i]))
+            .map(|term| {
+                let df = docs.iter().filter(|d| d.contains(term)).count();
+                (term.as_str(), df)
+            })
+            .collect();
+
+        docs.iter()
+            .map(|doc| {
+                let dl = doc.len() as f32;
+                query_terms
+                    .iter()
+                    .map(|term| {
+                        let tf = doc.iter().filter(|t| *t == term).count() as f32;
+                        if tf == 0.0 {
+                            return 0.0;
+                        }
+                        let n_t = doc_freq[term.as_str()] as f32;
+                        let idf = ((n as f32 - n_t + 0.5) / (n_t + 0.5) + 1.0).ln();
+                        idf * (tf * (K1 + 1.0)) / (tf + K1 * (1.0 - B + B * dl / avgdl))
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Like `search`, but lets the caller choose which ranking signal(s) to use and how many
+    /// results to return. `search` is a thin wrapper over this in `SearchMode::Semantic`, so its
+    /// behavior (including the `"similarity"` field on each returned `Entry::Json`) is unchanged.
+    pub async fn search_with_mode(
+        &self,
+        query: &Entry,
+        mode: SearchMode,
+        n: usize,
+    ) -> Result<Vec<Entry>, FileSystemError> {
+        if self.entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query_text = query.to_string();
+        // Pull more candidates than `n` from each signal so fusion has something to fuse even
+        // when the caller only wants a handful of final results.
+        let candidate_pool = n.max(10);
+
+        let semantic: Option<Vec<(usize, f32)>> = match mode {
+            SearchMode::Keyword => None,
+            SearchMode::Semantic | SearchMode::Hybrid => {
+                let embedding = self
+                    .embedder
+                    .generate_query_embeddings(&query_text)
+                    .await
+                    .map_err(FileSystemError::EmbeddingError)?;
+                Some(
+                    self.ann_search(&embedding, candidate_pool)
+                        .unwrap_or_else(|| {
+                            let coarse = self.scored_candidates(&embedding);
+                            self.rerank_top(&embedding, &coarse, candidate_pool)
+                        }),
+                )
+            }
+        };
+
+        let keyword: Option<Vec<(usize, f32)>> = match mode {
+            SearchMode::Semantic => None,
+            SearchMode::Keyword | SearchMode::Hybrid => {
+                let scores = self.bm25_scores(&query_text);
+                Some(
+                    rank_by(&scores)
+                        .into_iter()
+                        .map(|i| (i, scores[i]))
+                        .collect(),
+                )
+            }
+        };
+
+        // Standard RRF damping constant; see e.g. Cormack et al., "Reciprocal Rank Fusion".
+        const RRF_C: f32 = 60.0;
+        let ranked: Vec<(usize, f32)> = match mode {
+            SearchMode::Semantic => semantic.clone().unwrap(),
+            SearchMode::Keyword => keyword.clone().unwrap(),
+            SearchMode::Hybrid => {
+                let rankings: Vec<Vec<usize>> = [semantic.as_ref(), keyword.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|ranked| ranked.iter().map(|&(i, _)| i).collect())
+                    .collect();
+                reciprocal_rank_fusion(&rankings, RRF_C)
+            }
+        };
+
+        let semantic_lookup: HashMap<usize, f32> = semantic.iter().flatten().cloned().collect();
+        let keyword_lookup: HashMap<usize, f32> = keyword.iter().flatten().cloned().collect();
+
+        let passages = ranked
+            .into_iter()
+            .take(n)
+            .map(|(idx, score)| {
+                let entry = &self.entries[idx];
+                debug!("Score: {}, passage: {}", score, entry.text);
+                let mut fields = json!({ "passage": entry.text });
+                match mode {
+                    SearchMode::Semantic => fields["similarity"] = json!(score),
+                    SearchMode::Keyword => fields["bm25"] = json!(score),
+                    SearchMode::Hybrid => {
+                        fields["rrf_score"] = json!(score);
+                        if let Some(s) = semantic_lookup.get(&idx) {
+                            fields["similarity"] = json!(s);
+                        }
+                        if let Some(k) = keyword_lookup.get(&idx) {
+                            fields["bm25"] = json!(k);
+                        }
+                    }
+                }
+                if let Some(source) = &entry.source {
+                    fields["source"] = json!(source.source_id);
+                    fields["range"] =
+                        json!({ "start": source.range.start, "end": source.range.end });
+                }
+                Entry::Json(fields)
+            })
+            .collect();
+        Ok(passages)
+    }
+
+    /// Below this many entries, a brute-force cosine scan is already fast enough that querying
+    /// the ANN index isn't worth its overhead; `ann_search` returns `None` under this so callers
+    /// fall back to an exact scan for small collections (and whenever no index exists yet).
+    const ANN_MIN_ENTRIES: usize = 256;
+
+    /// Approximate top-`n` nearest neighbors of `embedding` from the HNSW index, or `None` if
+    /// there's no index yet or `entries` is too small for it to be worth using (see
+    /// `ANN_MIN_ENTRIES`) — callers fall back to `brute_force_top_n`'s exact scan in that case.
+    fn ann_search(&self, embedding: &[f32], n: usize) -> Option<Vec<(usize, f32)>> {
+        if self.entries.len() < Self::ANN_MIN_ENTRIES {
+            return None;
+        }
+        let index = self.index.as_ref()?;
+        let query = normalize(embedding);
+        let results = index.search(&query, n).ok()?;
+        Some(
+            results
+                .keys
+                .into_iter()
+                .zip(results.distances)
+                // usearch reports inner-product *distance* (`1 - dot`); convert back to similarity.
+                .map(|(key, distance)| (key as usize, 1.0 - distance))
+                .collect(),
+        )
+    }
+}
+
+/// Lowercases and splits `text` into alphanumeric tokens, for `FileSystem::bm25_scores`.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Indices into `scores`, sorted by descending score (the rank-0 entry is the best match).
+fn rank_by(scores: &[f32]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..scores.len()).collect();
+    indices.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap());
+    indices
+}
+
+/// Fuses multiple rankings of the same document set via Reciprocal Rank Fusion
+/// (`rrf(doc) = Σ 1 / (c + rank)`), returning documents sorted by descending fused score.
+fn reciprocal_rank_fusion(rankings: &[Vec<usize>], c: f32) -> Vec<(usize, f32)> {
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+    for ranking in rankings {
+        for (rank, &doc) in ranking.iter().enumerate() {
+            *scores.entry(doc).or_insert(0.0) += 1.0 / (c + rank as f32 + 1.0);
+        }
+    }
+    let mut scored: Vec<(usize, f32)> = scores.into_iter().collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    scored
+}
+
+/// A known programming language whose grammar `chunk_document` can split on syntactic boundaries
+/// (functions, classes) instead of falling back to paragraph/sentence splitting.
+pub enum SourceLanguage {
+    Prose,
+    Rust,
+    Python,
+    JavaScript,
+}
+
+/// Splits `text` into spans no larger than `max_tokens`, on syntactic boundaries for a known
+/// `language` and on paragraph/sentence boundaries otherwise, then carries the trailing
+/// `overlap_tokens` *characters* of each chunk onto the front of the next so a passage spanning a
+/// chunk boundary still reads whole in at least one chunk. Each span is paired with its byte
+/// range in the original `text` (the un-overlapped range, for traceability back to the source).
+fn chunk_document(
+    text: &str,
+    language: &SourceLanguage,
+    max_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<(String, Range<usize>)> {
+    fn prose_chunks(text: &str, max_tokens: usize) -> Vec<(String, Range<usize>)> {
+        TextSplitter::new(max_tokens)
+            .chunk_indices(text)
+            .map(|(offset, chunk)| (chunk.to_string(), offset..offset + chunk.len()))
+            .collect()
+    }
+
+    let grammar = match language {
+        SourceLanguage::Prose => None,
+        SourceLanguage::Rust => Some(tree_sitter_rust::LANGUAGE.into()),
+        SourceLanguage::Python => Some(tree_sitter_python::LANGUAGE.into()),
+        SourceLanguage::JavaScript => Some(tree_sitter_javascript::LANGUAGE.into()),
+    };
+
+    let raw = match grammar {
+        None => prose_chunks(text, max_tokens),
+        Some(grammar) => match CodeSplitter::new(grammar, max_tokens) {
+            Ok(splitter) => splitter
+                .chunk_indices(text)
+                .map(|(offset, chunk)| (chunk.to_string(), offset..offset + chunk.len()))
+                .collect(),
+            // The grammar couldn't parse this text (e.g. it isn't actually valid source in that
+            // language); fall back to prose splitting rather than failing the whole document.
+            Err(_) => prose_chunks(text, max_tokens),
+        },
+    };
+
+    if overlap_tokens == 0 {
+        return raw;
+    }
+
+    let mut overlapped = Vec::with_capacity(raw.len());
+    let mut carry = String::new();
+    for (chunk, range) in raw {
+        let with_carry = if carry.is_empty() {
+            chunk.clone()
+        } else {
+            format!("{carry}{chunk}")
+        };
+        // Char-based (not byte-based) so the carried slice can't land mid-UTF-8-codepoint.
+        carry = chunk
+            .chars()
+            .rev()
+            .take(overlap_tokens)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
             .collect();
-        top_results
+        overlapped.push((with_carry, range));
+    }
+    overlapped
+}
+
+/// Builds an empty HNSW index over `dimensions`-wide vectors compared by inner product, which
+/// equals cosine similarity as long as every inserted vector is unit-normalized (see
+/// `normalize`).
+fn new_index_with_dimensions(dimensions: usize) -> Index {
+    let options = IndexOptions {
+        dimensions,
+        metric: MetricKind::IP,
+        quantization: ScalarKind::F16,
+        connectivity: 0,     // zero for auto
+        expansion_add: 0,    // zero for auto
+        expansion_search: 0, // zero for auto
+        multi: true,
+    };
+    new_index(&options).unwrap()
+}
+
+/// Scales `embedding` to unit length so an inner-product index reduces to cosine similarity.
+fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return embedding.to_vec();
+    }
+    embedding.iter().map(|v| v / norm).collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+    {
+        f32::cosine(a, b).unwrap_or(0.0) as f32
+    }
+    #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+    {
+        f32::cosine(a, b).unwrap_or(0.0)
+    }
+}
+
+/// Fixed, shared range every embedding is quantized against for `QuantizationMode::Int8`. Valid
+/// only for unit-normalized input, whose components always fall within `[-1.0, 1.0]` regardless
+/// of which vector it is — using the same range for every vector (instead of each one's own
+/// `[min, max]`) is what makes `quantized_similarity`'s direct byte-cosine comparison meaningful
+/// across different embeddings.
+const INT8_QUANT_RANGE: std::ops::Range<f32> = -1.0..1.0;
+
+/// Linearly maps each component of the (already unit-normalized) `embedding` from the shared
+/// `INT8_QUANT_RANGE` onto `i8`, for `QuantizationMode::Int8`.
+fn quantize_int8(embedding: &[f32]) -> QuantizedEmbedding {
+    let span = INT8_QUANT_RANGE.end - INT8_QUANT_RANGE.start;
+    let bytes = embedding
+        .iter()
+        .map(|&v| {
+            let v = v.clamp(INT8_QUANT_RANGE.start, INT8_QUANT_RANGE.end);
+            ((((v - INT8_QUANT_RANGE.start) / span) * 255.0).round() - 128.0).clamp(-128.0, 127.0)
+                as i8
+        })
+        .collect();
+    QuantizedEmbedding::Int8 { bytes }
+}
+
+/// Packs one sign bit per dimension of `embedding`, 8 to a byte, for `QuantizationMode::Binary`.
+fn quantize_binary(embedding: &[f32]) -> QuantizedEmbedding {
+    let dims = embedding.len();
+    let mut bits = vec![0u8; dims.div_ceil(8)];
+    for (i, &v) in embedding.iter().enumerate() {
+        if v >= 0.0 {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+    QuantizedEmbedding::Binary { bits, dims }
+}
+
+/// Compares two `QuantizedEmbedding`s of the same variant: an int8 cosine estimate, or a
+/// Hamming-distance-based agreement fraction for binary vectors. Gated the same way `cosine`
+/// is between the vectorized `simsimd` path and the portable `arm_compat` fallback.
+fn quantized_similarity(a: &QuantizedEmbedding, b: &QuantizedEmbedding) -> f32 {
+    match (a, b) {
+        (
+            QuantizedEmbedding::Int8 { bytes: a_bytes, .. },
+            QuantizedEmbedding::Int8 { bytes: b_bytes, .. },
+        ) => {
+            #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+            {
+                i8::cosine(a_bytes, b_bytes).unwrap_or(0.0) as f32
+            }
+            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+            {
+                let dot: i64 = a_bytes
+                    .iter()
+                    .zip(b_bytes)
+                    .map(|(&x, &y)| x as i64 * y as i64)
+                    .sum();
+                let norm_a = (a_bytes
+                    .iter()
+                    .map(|&x| (x as i64) * (x as i64))
+                    .sum::<i64>() as f64)
+                    .sqrt();
+                let norm_b = (b_bytes
+                    .iter()
+                    .map(|&x| (x as i64) * (x as i64))
+                    .sum::<i64>() as f64)
+                    .sqrt();
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    0.0
+                } else {
+                    (dot as f64 / (norm_a * norm_b)) as f32
+                }
+            }
+        }
+        (
+            QuantizedEmbedding::Binary { bits: a_bits, dims },
+            QuantizedEmbedding::Binary { bits: b_bits, .. },
+        ) => {
+            #[cfg(not(any(target_arch = "arm", target_arch = "aarch64")))]
+            let distance = u8::hamming(a_bits, b_bits).unwrap_or(0.0);
+            #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+            let distance = a_bits
+                .iter()
+                .zip(b_bits)
+                .map(|(&x, &y)| (x ^ y).count_ones() as f64)
+                .sum::<f64>();
+            1.0 - (distance as f32 / *dims as f32)
+        }
+        // Mismatched variants only happen if `quantization` changed mid-lifetime; treat as
+        // unrelated rather than panicking.
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        let normalized = normalize(&[3.0, 4.0]);
+        let norm: f32 = normalized.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn normalize_leaves_a_zero_vector_alone() {
+        assert_eq!(normalize(&[0.0, 0.0, 0.0]), vec![0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn cosine_of_identical_unit_vectors_is_one() {
+        let v = normalize(&[1.0, 2.0, 3.0]);
+        assert!((cosine(&v, &v) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn quantize_int8_round_trips_similarity_ranking_across_different_vectors() {
+        // Two normalized embeddings quantized against the same shared range should still rank
+        // "closer to a" above "closer to c" after quantization, which a per-vector min/max
+        // would not guarantee (see chunk6-6).
+        let a = normalize(&[1.0, 0.0, 0.0]);
+        let b = normalize(&[0.9, 0.1, 0.0]);
+        let c = normalize(&[-1.0, 0.0, 0.0]);
+
+        let qa = quantize_int8(&a);
+        let qb = quantize_int8(&b);
+        let qc = quantize_int8(&c);
+
+        let sim_ab = quantized_similarity(&qa, &qb);
+        let sim_ac = quantized_similarity(&qa, &qc);
+        assert!(sim_ab > sim_ac);
+    }
+
+    #[test]
+    fn quantize_binary_and_similarity_agree_on_identical_vectors() {
+        let v = normalize(&[1.0, -1.0, 1.0, -1.0]);
+        let q = quantize_binary(&v);
+        assert!((quantized_similarity(&q, &q) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tokenize_lowercases_and_splits_on_non_alphanumeric() {
+        assert_eq!(tokenize("Hello, World! 123"), vec!["hello", "world", "123"]);
+    }
+
+    #[test]
+    fn rank_by_orders_indices_by_descending_score() {
+        assert_eq!(rank_by(&[0.1, 0.9, 0.5]), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn reciprocal_rank_fusion_rewards_documents_ranked_highly_in_both_lists() {
+        // Doc 0 is top of both rankings, so its fused score is unambiguously the highest
+        // (unlike a tie between docs 0 and 1, which would make the winner depend on
+        // HashMap iteration order).
+        let semantic_ranking = vec![0, 1, 2];
+        let keyword_ranking = vec![0, 2, 1];
+        let fused = reciprocal_rank_fusion(&[semantic_ranking, keyword_ranking], 60.0);
+        assert_eq!(fused[0].0, 0);
+        assert_ne!(fused.last().unwrap().0, 0);
     }
 }
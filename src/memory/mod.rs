@@ -26,6 +26,17 @@ impl ProgramMemory {
             stack: Stack::new(),
         }
     }
+
+    /// Creates a `ProgramMemory` whose content-addressed LLM-output cache is persisted to
+    /// `path`, so repeated or deterministic (temperature 0) workflows skip redundant model
+    /// calls across process runs.
+    pub fn with_persistent_cache(path: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        Ok(ProgramMemory {
+            cache: Cache::with_persistent_cache(path)?,
+            file_system: FileSystem::new(),
+            stack: Stack::new(),
+        })
+    }
 }
 
 impl Default for ProgramMemory {
@@ -63,6 +74,14 @@ impl ProgramMemory {
     pub fn write(&mut self, key: types::ID, value: types::Entry) {
         self.cache.set(key, value);
     }
+    /// Read a content-addressed memoized LLM output, keyed by `cache::digest_request`.
+    pub fn get_memoized(&self, digest: &str) -> Option<types::Entry> {
+        self.cache.get_memoized(digest)
+    }
+    /// Store a content-addressed memoized LLM output, keyed by `cache::digest_request`.
+    pub fn set_memoized(&mut self, digest: String, value: types::Entry) {
+        self.cache.set_memoized(digest, value);
+    }
     /// Push to the stack.
     pub fn push(&mut self, key: types::ID, value: types::Entry) {
         self.stack.push(key, value);
@@ -111,4 +130,11 @@ impl ProgramMemory {
             Err(_) => None,
         }
     }
+
+    /// Cosine similarity between `query` and `candidate`, embedding both via the same embedder
+    /// backing `search`/`have_similar`. Used to re-rank non-stored text (e.g. web search hits)
+    /// by semantic relevance without inserting it into the file system first.
+    pub async fn semantic_similarity(&self, query: &str, candidate: &str) -> Option<f32> {
+        self.file_system.similarity(query, candidate).await.ok()
+    }
 }
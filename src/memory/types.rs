@@ -1,8 +1,44 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::ops::Range;
 pub type ID = String;
 pub type StackPage = Vec<Entry>;
-pub type FilePage = (String, Vec<f32>);
+
+/// A single stored `FileSystem` entry: its text, embedding, and, if it was added via
+/// `FileSystem::add_document`, which source document it was chunked from.
+#[derive(Clone)]
+pub struct FilePage {
+    pub text: String,
+    pub embedding: Vec<f32>,
+    pub source: Option<ChunkSource>,
+    /// Compact alternate form of `embedding`, populated when `FileSystem`'s `QuantizationMode`
+    /// is `Int8` or `Binary` so the first-pass candidate scan can scan it instead of the full
+    /// `Vec<f32>`. `None` under `QuantizationMode::Full`.
+    pub quantized: Option<QuantizedEmbedding>,
+}
+
+/// Where a `FilePage` came from: which source document it was split out of, and its byte range
+/// within that document's original text (before any overlap carried from a neighboring chunk).
+#[derive(Clone)]
+pub struct ChunkSource {
+    pub source_id: String,
+    pub range: Range<usize>,
+}
+
+/// A compact alternate representation of an embedding, traded for ranking precision in exchange
+/// for a smaller memory footprint and a cheaper first-pass comparison; see
+/// `FileSystem::scored_candidates`/`rerank_top`.
+#[derive(Clone)]
+pub enum QuantizedEmbedding {
+    /// Per-component int8 scalar quantization: each component of the unit-normalized embedding
+    /// linearly mapped from the shared, fixed `[-1.0, 1.0]` range onto `i8` (see
+    /// `FileSystem::quantize`). Every embedding uses the same range so `quantized_similarity`'s
+    /// direct byte comparison approximates real cosine similarity; a per-vector range would give
+    /// each embedding its own affine shift, making cross-vector comparisons meaningless.
+    Int8 { bytes: Vec<i8> },
+    /// One sign bit per dimension, bit-packed 8 to a byte; compared via Hamming distance.
+    Binary { bits: Vec<u8>, dims: usize },
+}
 
 /// Entry is an enum that can be either a String or a Json Value.
 /// It is used for I/O operations in the memory module.
@@ -1,4 +1,5 @@
 use crate::program::io::{Input, InputValue, Output};
+use crate::program::models::Model;
 use crate::ProgramMemory;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -50,9 +51,55 @@ pub struct CustomToolTemplate {
     pub mode: CustomToolModeTemplate,
 }
 
+/// Controls which tool(s), if any, a `FunctionCalling`/`FunctionCallingRaw` task is allowed
+/// to call. Deserialized from a plain string: `"auto"` (the model decides), `"none"` (no tool
+/// use at all), `"required"` (some tool must be called, model picks which), or any other value
+/// naming a tool to force.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ToolChoice {
+    /// Let the model decide whether and which tool to call.
+    #[default]
+    Auto,
+    /// Disable tool use for this task.
+    None,
+    /// Require some tool call, leaving the choice of which one to the model.
+    Required,
+    /// Force the model to use exactly this tool, by name.
+    Force(String),
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        Ok(match raw.as_str() {
+            "auto" => ToolChoice::Auto,
+            "none" => ToolChoice::None,
+            "required" => ToolChoice::Required,
+            _ => ToolChoice::Force(raw),
+        })
+    }
+}
+
+fn default_config_version() -> u32 {
+    1
+}
+
 /// Configuration for the workflow
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    /// Schema version of this config. Workflow JSON written before this field existed has no
+    /// `version` key and defaults to `1` (the original shape); `2` and above may carry newer
+    /// fields such as `model`, letting older binaries keep loading newer workflow files instead
+    /// of hard-failing on an unrecognized key.
+    #[serde(default = "default_config_version")]
+    pub version: u32,
+    /// Optional per-workflow model override, including arbitrary `Model::Custom` models that
+    /// aren't hard-coded into this crate.
+    #[serde(default)]
+    pub model: Option<Model>,
     /// Maximum number of steps to execute. Program halts afterwards.
     pub max_steps: u32,
     /// Maximum execution time in seconds. Program halts afterwards.
@@ -62,11 +109,38 @@ pub struct Config {
     pub tools: Vec<String>,
     /// A list of custom tools that user can define within workflow.
     pub custom_tools: Option<Vec<CustomToolTemplate>>,
+    /// Memoize `Generation`/`FunctionCalling` task outputs by a content-addressed hash of the
+    /// request, skipping the model call entirely on a repeat. See `ProgramMemory::get_memoized`.
+    #[serde(default)]
+    pub cache: bool,
+    /// Maximum number of tool-call round-trips allowed within a single `FunctionCalling` /
+    /// `FunctionCallingRaw` task before the agent loop is forced to return its last answer.
+    #[serde(default)]
+    pub max_tool_iterations: Option<u32>,
+    /// Maximum number of retries for a `Generation`/`FunctionCalling` request that fails
+    /// (network error, rate limit, transient server error), with exponential backoff and
+    /// jitter between attempts. Defaults to `0` (no retry) when omitted. Independent of
+    /// `GeminiExecutor`'s own 429/5xx-aware retry (see `gem_api.rs`'s `max_retries`), which
+    /// always applies regardless of this setting.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
     /// Maximum number of tokens for LLMs to generate per run.
     pub max_tokens: Option<i32>,
-    pub temperature: Option<f64>,   // Add temperature field
-    pub top_k: Option<i32>,         // Add top_k field
-    pub logits: Option<bool>,       // Add logits field
+    pub temperature: Option<f64>, // Add temperature field
+    pub top_k: Option<i32>,       // Add top_k field
+    pub logits: Option<bool>,     // Add logits field
+    /// Ollama's context window size (`num_ctx`), in tokens. Ollama's own default (4096) silently
+    /// truncates long workflow prompts; set this to raise it. Ignored by non-Ollama providers.
+    #[serde(default)]
+    pub num_ctx: Option<u64>,
+    /// Nucleus sampling threshold, passed straight through to Ollama's `top_p` option. Ignored
+    /// by non-Ollama providers.
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Fixes Ollama's sampling seed for deterministic runs across identical prompts. Ignored by
+    /// non-Ollama providers.
+    #[serde(default)]
+    pub seed: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -129,6 +203,15 @@ pub struct Task {
     pub outputs: Vec<Output>,
     /// Schema for structured outputs.
     pub schema: Option<String>,
+    /// Which tool the `FunctionCalling`/`FunctionCallingRaw` operator should use, defaults to
+    /// `ToolChoice::Auto` when omitted.
+    #[serde(default)]
+    pub tool_choice: Option<ToolChoice>,
+    /// Bypasses the per-(tool, arguments) result cache for this task's `FunctionCalling`/
+    /// `FunctionCallingRaw` tool calls, forcing every call to hit the tool even if an identical
+    /// one was already served this run.
+    #[serde(default)]
+    pub no_cache: bool,
 }
 
 impl Task {
@@ -180,6 +263,22 @@ pub enum PostProcessType {
     TrimEnd,
     ToLower,
     ToUpper,
+    /// Reinterprets `return_string` as an integer, re-emitting its canonical decimal form.
+    /// Errors out (rather than passing the original text through) if it doesn't parse.
+    ParseInt,
+    /// Reinterprets `return_string` as a float, re-emitting its canonical decimal form. Errors
+    /// out if it doesn't parse.
+    ParseFloat,
+    /// Reinterprets `return_string` as a bool (`"true"`/`"false"`, case-insensitive). Errors out
+    /// if it doesn't parse.
+    ParseBool,
+    /// Parses `return_string` as a timestamp and reformats it with a chrono format string.
+    /// `rhs` is the required output format; `lhs`, if set, is the input format to parse with
+    /// (`chrono::NaiveDateTime::parse_from_str`), otherwise the input is parsed as RFC 3339.
+    TimestampFmt,
+    /// Parses `return_string` as JSON and extracts the field at the dotted path in `lhs`,
+    /// re-emitting it as a string (its own text if it's a JSON string, otherwise its JSON form).
+    JsonExtract,
 }
 
 #[derive(Debug, Deserialize)]
@@ -236,7 +335,12 @@ impl Expression {
                 input.parse::<f64>().unwrap() <= expected.parse::<f64>().unwrap()
             }
             Expression::HaveSimilar => {
-                let res = memory.unwrap().have_similar(expected, Some(0.95)).await;
+                // `expected` is the configured similarity threshold (e.g. `~= 0.85`), not a
+                // literal comparison value; `input` (the resolved left-hand side) is what gets
+                // compared against the stored `FilePage` embeddings. Falls back to
+                // `have_similar`'s own default threshold when `expected` isn't a valid float.
+                let threshold = expected.trim().parse::<f32>().ok();
+                let res = memory.unwrap().have_similar(input, threshold).await;
                 res.unwrap_or(false)
             }
         }
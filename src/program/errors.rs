@@ -8,6 +8,24 @@ pub enum CustomError {
     EmbeddingError(EmbeddingError),
     ToolError(ToolError),
     ExecutionError(ExecutionError),
+    WorkflowError(WorkflowError),
+}
+
+/// A diagnostic raised by `Workflow::validate`, pinpointing a structural problem in the task
+/// graph that would otherwise only surface as a confusing failure mid-execution.
+#[derive(Debug, PartialEq)]
+pub enum WorkflowError {
+    /// An edge's `source`, `target`, `fallback` or `target_if_not` names a task id that
+    /// doesn't exist among `tasks`.
+    DanglingReference { edge_source: String, missing_task: String },
+    /// A task is never reachable by walking the edge graph from the entry step.
+    UnreachableTask(String),
+    /// A cycle was found in the edge graph where no edge along the cycle carries a
+    /// `Condition`, so nothing but `max_steps`/`max_time` can ever break out of it.
+    UnterminatedCycle(Vec<String>),
+    /// A task writes to a memory key that no task ever reads as an input and that the
+    /// workflow's return value doesn't read either.
+    DeadOutput { task_id: String, key: String },
 }
 
 #[allow(dead_code)]
@@ -38,11 +56,19 @@ pub enum ExecutionError {
     InvalidInput,
     GenerationFailed(String),
     FunctionCallFailed(String),
+    /// Raised before a `FunctionCalling`/`FunctionCallingRaw` task ever calls its provider, when
+    /// `Model::supports_tool_calling` is false for the selected model, so the failure is explicit
+    /// instead of surfacing as an opaque provider error partway through the tool loop.
+    FunctionCallingUnsupported(String),
     VectorSearchFailed,
     StringCheckFailed,
     SamplingError,
     InvalidGetAllError,
     UnexpectedOutput,
+    /// A `Generation`/`FunctionCalling` request still failed after `Config.max_retries` retries.
+    /// Returned in place of `GenerationFailed`/`FunctionCallFailed` whenever `max_retries` is
+    /// set; every error is retried, not only 429/5xx responses.
+    RetriesExhausted(String),
 }
 
 impl fmt::Display for CustomError {
@@ -52,6 +78,32 @@ impl fmt::Display for CustomError {
             CustomError::EmbeddingError(err) => write!(f, "Embedding error: {}", err),
             CustomError::ToolError(err) => write!(f, "Tool error: {}", err),
             CustomError::ExecutionError(err) => write!(f, "Execution error: {}", err),
+            CustomError::WorkflowError(err) => write!(f, "Workflow error: {}", err),
+        }
+    }
+}
+
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorkflowError::DanglingReference { edge_source, missing_task } => write!(
+                f,
+                "edge from [{}] references non-existent task [{}]",
+                edge_source, missing_task
+            ),
+            WorkflowError::UnreachableTask(task_id) => {
+                write!(f, "task [{}] is never reachable from the entry step", task_id)
+            }
+            WorkflowError::UnterminatedCycle(cycle) => write!(
+                f,
+                "cycle {} has no conditional edge that can break out of it",
+                cycle.join(" -> ")
+            ),
+            WorkflowError::DeadOutput { task_id, key } => write!(
+                f,
+                "task [{}] writes to key [{}] which no task or return value reads",
+                task_id, key
+            ),
         }
     }
 }
@@ -97,6 +149,11 @@ impl fmt::Display for ExecutionError {
             ExecutionError::UnexpectedOutput => write!(f, "Unexpected output from command"),
             ExecutionError::GenerationFailed(detail) => write!(f, "Text generation failed {}", detail),
             ExecutionError::FunctionCallFailed(detail) => write!(f, "Function call failed {}", detail),
+            ExecutionError::FunctionCallingUnsupported(model) => write!(
+                f,
+                "Model {} does not support function calling",
+                model
+            ),
             ExecutionError::VectorSearchFailed => write!(f, "Vector search failed"),
             ExecutionError::StringCheckFailed => write!(f, "Vector search failed"),
             ExecutionError::SamplingError => {
@@ -106,6 +163,9 @@ impl fmt::Display for ExecutionError {
                 f,
                 "Error sampling because value is not get_all compatible (array)"
             ),
+            ExecutionError::RetriesExhausted(detail) => {
+                write!(f, "Gave up after exhausting retries: {}", detail)
+            }
         }
     }
 }
@@ -115,6 +175,7 @@ impl Error for FileSystemError {}
 impl Error for EmbeddingError {}
 impl Error for ToolError {}
 impl Error for ExecutionError {}
+impl Error for WorkflowError {}
 
 impl From<FileSystemError> for CustomError {
     fn from(err: FileSystemError) -> CustomError {
@@ -139,3 +200,9 @@ impl From<ExecutionError> for CustomError {
         CustomError::ExecutionError(err)
     }
 }
+
+impl From<WorkflowError> for CustomError {
+    fn from(err: WorkflowError) -> CustomError {
+        CustomError::WorkflowError(err)
+    }
+}
@@ -1,8 +1,12 @@
 use super::atomics::*;
 use super::io::*;
 use super::models::*;
-use super::workflow::Workflow;
+use super::workflow::{TaskStatus, Workflow};
+use crate::api_interface::embeddings::{
+    OllamaRestEmbedder, OpenAICompatibleEmbedder, RestEmbedder,
+};
 use crate::api_interface::gem_api::GeminiExecutor;
+use crate::api_interface::language_model::{LanguageModelProvider, OllamaProvider};
 use crate::api_interface::open_router::OpenRouterExecutor;
 use crate::api_interface::openai_api::OpenAIExecutor;
 use crate::memory::types::Entry;
@@ -20,25 +24,86 @@ use std::time::Instant;
 
 use colored::*;
 
-use base64::prelude::*;
 use log::{debug, error, info, warn};
 use rand::seq::SliceRandom;
 
 use ollama_rs::{
     error::OllamaError,
-    generation::chat::request::ChatMessageRequest,
-    generation::chat::ChatMessage,
     generation::completion::request::GenerationRequest,
     generation::functions::tools::StockScraper,
     generation::functions::tools::Tool,
-    generation::functions::{
-        DDGSearcher, FunctionCallRequest, LlamaFunctionCall, OpenAIFunctionCall, Scraper,
-    },
-    generation::options::GenerationOptions,
-    generation::parameters::FormatType,
+    generation::functions::{DDGSearcher, OpenAIFunctionCall, Scraper},
     Ollama,
 };
 
+/// Retries `attempt` up to `max_retries` times (on top of the first try) with exponential
+/// backoff and jitter between tries, giving a transient provider failure (rate limit, momentary
+/// network or server error) a chance to clear before `Config.max_retries` is exhausted. Every
+/// provider's error collapses to `OllamaError` by the time it reaches `Executor`, so unlike
+/// `GeminiExecutor::post_with_retry` (see `gem_api.rs`), which can check the HTTP status before
+/// deciding to retry, this treats any error as transient and retryable.
+async fn retry_with_backoff<T, F, Fut>(max_retries: u32, mut attempt: F) -> Result<T, OllamaError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, OllamaError>>,
+{
+    let mut last_err = None;
+    for try_idx in 0..=max_retries {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                if try_idx < max_retries {
+                    let jitter = rand::thread_rng().gen_range(0..250);
+                    let delay =
+                        std::time::Duration::from_millis(500 * 2u64.pow(try_idx.min(10)) + jitter);
+                    tokio::time::sleep(delay).await;
+                }
+                last_err = Some(e);
+            }
+        }
+    }
+    Err(last_err.expect("loop runs at least once"))
+}
+
+/// Builds the `Ollama` client for `host`/`port`, attaching `Authorization: Bearer <token>` from
+/// `OLLAMA_API_KEY` when it's set in the environment, so requests against an authenticated or
+/// remote Ollama endpoint (behind a reverse proxy, or on a shared team host) succeed the same
+/// way unauthenticated localhost requests always have. Absence of the variable leaves today's
+/// behavior unchanged.
+fn build_ollama_client(host: &str, port: u16) -> Ollama {
+    match std::env::var("OLLAMA_API_KEY") {
+        Ok(key) if !key.is_empty() => {
+            let mut headers = reqwest::header::HeaderMap::new();
+            headers.insert(
+                reqwest::header::AUTHORIZATION,
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", key))
+                    .expect("OLLAMA_API_KEY must be a valid HTTP header value"),
+            );
+            let client = reqwest::Client::builder()
+                .default_headers(headers)
+                .build()
+                .expect("failed to build authenticated Ollama HTTP client");
+            Ollama::new_with_client(host.to_string(), port, client)
+        }
+        _ => Ollama::new(host.to_string(), port),
+    }
+}
+
+/// Splits a raw-mode completion from `agentic_function_call`'s tool-request turn into its
+/// individual tool calls. The model may request several independent tool calls in one turn;
+/// `handle_raw_mode` joins each call's JSON with a blank line. Returns `None` (telling the caller
+/// to treat `raw_call` as the model's final answer instead of a tool request) if there's nothing
+/// to parse or any segment isn't valid JSON.
+fn parse_raw_tool_calls(raw_call: &str) -> Option<Vec<Value>> {
+    let calls: Option<Vec<Value>> = raw_call
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| serde_json::from_str::<Value>(segment).ok())
+        .collect();
+    calls.filter(|calls| !calls.is_empty())
+}
+
 fn log_colored(msg: &str) {
     let colors = ["red", "green", "yellow", "blue", "magenta", "cyan"];
 
@@ -60,31 +125,85 @@ fn log_colored(msg: &str) {
 pub struct Executor {
     model: Model,
     llm: Ollama,
+    /// Host the `llm` client talks to, kept around (alongside `ollama_port`) so
+    /// `generate_embeddings` can point its own REST client at the same Ollama server instead of
+    /// assuming `http://localhost`.
+    ollama_host: String,
+    ollama_port: u16,
+    /// Gate applied to `execute_`-prefixed tool calls; forwarded to `OpenAIExecutor` (the only
+    /// provider that currently supports it) via `with_confirmation_callback`. `None` runs every
+    /// tool immediately, unchanged from before this existed. See `Executor::with_confirmation_callback`.
+    confirmation_callback: Option<Arc<dyn Fn(&str, &Value) -> bool + Send + Sync>>,
 }
 
 impl Executor {
-    /// Create a new Executor with a default Ollama instance.
+    /// Create a new Executor with a default Ollama instance at `http://localhost:11434`,
+    /// authenticated with `OLLAMA_API_KEY` if that variable is set (see `build_ollama_client`).
     pub fn new(model: Model) -> Self {
-        Self {
-            model,
-            llm: Ollama::default(),
-        }
+        Self::new_at(model, "http://localhost", 11434)
     }
 
-    /// Create a new Executor for an Ollama instance at a specific host and port.
+    /// Create a new Executor for an Ollama instance at a specific host and port, authenticated
+    /// with `OLLAMA_API_KEY` if that variable is set (see `build_ollama_client`), so a team's
+    /// shared/remote Ollama server behind a reverse proxy works the same way localhost does.
     pub fn new_at(model: Model, host: &str, port: u16) -> Self {
         Self {
             model,
-            llm: Ollama::new(host, port),
+            llm: build_ollama_client(host, port),
+            ollama_host: host.to_string(),
+            ollama_port: port,
+            confirmation_callback: None,
         }
     }
 
+    /// Registers a human-in-the-loop gate for `execute_`-prefixed tool calls (see
+    /// `requires_confirmation` in `openai_api.rs`): called with the tool's name and parsed
+    /// arguments before it runs, and the call is skipped (not aborted) if it returns `false`.
+    /// Only takes effect when `self.model` routes to `ModelProvider::OpenAI`, since
+    /// `OpenAIExecutor` is the only provider that currently implements the gate.
+    pub fn with_confirmation_callback(
+        mut self,
+        callback: impl Fn(&str, &Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirmation_callback = Some(Arc::new(callback));
+        self
+    }
+
     /// Executes the workflow
     pub async fn execute(
         &self,
         input: Option<&Entry>,
         workflow: &Workflow,
         memory: &mut ProgramMemory,
+    ) -> Result<String, ExecutionError> {
+        self.execute_inner(input, workflow, memory, &mut None).await
+    }
+
+    /// Executes the workflow exactly like `execute`, additionally recording which task each
+    /// edge's source ran and whether it succeeded or failed, and returning that alongside the
+    /// result as a Graphviz DOT digraph (see `Workflow::to_dot_with_trace`). The trace is built
+    /// regardless of whether the run ultimately succeeds, so a failed workflow can still be
+    /// rendered to see exactly how far it got and which branch each condition took.
+    pub async fn execute_traced(
+        &self,
+        input: Option<&Entry>,
+        workflow: &Workflow,
+        memory: &mut ProgramMemory,
+    ) -> (Result<String, ExecutionError>, String) {
+        let mut trace = Some(HashMap::new());
+        let result = self
+            .execute_inner(input, workflow, memory, &mut trace)
+            .await;
+        let dot = workflow.to_dot_with_trace(&trace.unwrap_or_default());
+        (result, dot)
+    }
+
+    async fn execute_inner(
+        &self,
+        input: Option<&Entry>,
+        workflow: &Workflow,
+        memory: &mut ProgramMemory,
+        trace: &mut Option<HashMap<String, TaskStatus>>,
     ) -> Result<String, ExecutionError> {
         let config = workflow.get_config();
         let max_steps = config.max_steps;
@@ -118,6 +237,17 @@ impl Executor {
                 if let Some(task) = workflow.get_tasks_by_id(&edge.source) {
                     let result = self.execute_task(task, memory.borrow_mut(), config).await;
 
+                    if let Some(trace) = trace {
+                        trace.insert(
+                            edge.source.clone(),
+                            if result.is_ok() {
+                                TaskStatus::Visited
+                            } else {
+                                TaskStatus::Failed
+                            },
+                        );
+                    }
+
                     current_step = if result.is_ok() {
                         //if there are conditions, check them
                         if let Some(condition) = &edge.condition {
@@ -246,6 +376,96 @@ impl Executor {
                     PostProcessType::Trim => return_string.trim().to_string(),
                     PostProcessType::TrimStart => return_string.trim_start().to_string(),
                     PostProcessType::TrimEnd => return_string.trim_end().to_string(),
+                    PostProcessType::ParseInt => match return_string.trim().parse::<i64>() {
+                        Ok(n) => n.to_string(),
+                        Err(e) => {
+                            return Err(ExecutionError::WorkflowFailed(format!(
+                                "parse_int post process failed on {:?}: {}",
+                                return_string, e
+                            )))
+                        }
+                    },
+                    PostProcessType::ParseFloat => match return_string.trim().parse::<f64>() {
+                        Ok(n) => n.to_string(),
+                        Err(e) => {
+                            return Err(ExecutionError::WorkflowFailed(format!(
+                                "parse_float post process failed on {:?}: {}",
+                                return_string, e
+                            )))
+                        }
+                    },
+                    PostProcessType::ParseBool => {
+                        match return_string.trim().to_lowercase().parse::<bool>() {
+                            Ok(b) => b.to_string(),
+                            Err(e) => {
+                                return Err(ExecutionError::WorkflowFailed(format!(
+                                    "parse_bool post process failed on {:?}: {}",
+                                    return_string, e
+                                )))
+                            }
+                        }
+                    }
+                    PostProcessType::TimestampFmt => {
+                        let Some(out_fmt) = process.rhs.clone() else {
+                            error!(
+                                "rhs (output format) is required for timestamp_fmt post process"
+                            );
+                            continue;
+                        };
+                        let parsed = match &process.lhs {
+                            Some(in_fmt) => {
+                                chrono::NaiveDateTime::parse_from_str(return_string.trim(), in_fmt)
+                                    .map(|dt| dt.and_utc())
+                            }
+                            None => chrono::DateTime::parse_from_rfc3339(return_string.trim())
+                                .map(|dt| dt.with_timezone(&chrono::Utc)),
+                        };
+                        match parsed {
+                            Ok(dt) => dt.format(&out_fmt).to_string(),
+                            Err(e) => {
+                                return Err(ExecutionError::WorkflowFailed(format!(
+                                    "timestamp_fmt post process failed on {:?}: {}",
+                                    return_string, e
+                                )))
+                            }
+                        }
+                    }
+                    PostProcessType::JsonExtract => {
+                        let Some(path) = process.lhs.clone() else {
+                            error!("lhs (json path) is required for json_extract post process");
+                            continue;
+                        };
+                        let json: Value = match serde_json::from_str(&return_string) {
+                            Ok(json) => json,
+                            Err(e) => {
+                                return Err(ExecutionError::WorkflowFailed(format!(
+                                    "json_extract post process failed to parse {:?} as JSON: {}",
+                                    return_string, e
+                                )))
+                            }
+                        };
+                        let mut current = &json;
+                        let mut found = true;
+                        for segment in path.split('.') {
+                            match current.get(segment) {
+                                Some(v) => current = v,
+                                None => {
+                                    found = false;
+                                    break;
+                                }
+                            }
+                        }
+                        if !found {
+                            return Err(ExecutionError::WorkflowFailed(format!(
+                                "json_extract post process: path {:?} not found in {}",
+                                path, return_string
+                            )));
+                        }
+                        current
+                            .as_str()
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| current.to_string())
+                    }
                 };
             }
         }
@@ -274,37 +494,76 @@ impl Executor {
             Operator::Generation => {
                 let prompt = self.fill_prompt(&task.messages, &input_map);
 
+                let digest = config.cache.then(|| {
+                    crate::memory::cache::digest_request(
+                        &prompt,
+                        &self.model.to_string(),
+                        config.temperature,
+                        config.top_k,
+                        config.max_tokens,
+                        task.schema.as_deref(),
+                    )
+                });
+
+                if let Some(cached) = digest.as_deref().and_then(|d| memory.get_memoized(d)) {
+                    debug!("Cache hit for task {}, skipping generation", &task.id);
+                    self.handle_output(task, cached, memory).await;
+                    return Ok(());
+                }
+
                 let result = self.generate_text(prompt, &task.schema, config).await;
                 if result.is_err() {
                     error!("Error generating text");
-                    return Err(ExecutionError::GenerationFailed(format!(
-                        "{:?}",
-                        result.err().unwrap()
-                    )));
+                    let detail = format!("{:?}", result.err().unwrap());
+                    return Err(if config.max_retries.is_some() {
+                        ExecutionError::RetriesExhausted(detail)
+                    } else {
+                        ExecutionError::GenerationFailed(detail)
+                    });
                 }
                 log_colored(
                     format!("Operator: {:?}. Output: {:?}", &task.operator, &result).as_str(),
                 );
                 let result_entry = Entry::try_value_or_str(&result.unwrap());
+                if let Some(digest) = digest {
+                    memory.set_memoized(digest, result_entry.clone());
+                }
                 self.handle_output(task, result_entry, memory).await;
             }
             Operator::FunctionCalling | Operator::FunctionCallingRaw => {
+                if !self.model.supports_tool_calling() {
+                    return Err(ExecutionError::FunctionCallingUnsupported(
+                        self.model.to_string(),
+                    ));
+                }
+
                 let prompt = self.fill_prompt(&task.messages, &input_map);
 
                 let raw_mode = matches!(task.operator, Operator::FunctionCallingRaw);
-                let result = self.function_call(prompt, config, raw_mode).await;
+                let result = self
+                    .agentic_function_call(prompt, task, memory, config, raw_mode)
+                    .await;
                 if result.is_err() {
                     error!("Error function calling");
-                    return Err(ExecutionError::FunctionCallFailed(format!(
-                        "{:?}",
-                        result.err().unwrap()
-                    )));
+                    let detail = format!("{:?}", result.err().unwrap());
+                    return Err(if config.max_retries.is_some() {
+                        ExecutionError::RetriesExhausted(detail)
+                    } else {
+                        ExecutionError::FunctionCallFailed(detail)
+                    });
                 }
 
+                let (answer, transcript) = result.unwrap();
                 log_colored(
-                    format!("Operator: {:?}. Output: {:?}", &task.operator, &result).as_str(),
+                    format!("Operator: {:?}. Output: {:?}", &task.operator, &answer).as_str(),
                 );
-                let result_entry = Entry::try_value_or_str(&result.unwrap());
+                if !transcript.is_empty() {
+                    memory.push(
+                        format!("{}.tool_transcript", &task.id),
+                        Entry::Json(json!(transcript)),
+                    );
+                }
+                let result_entry = Entry::try_value_or_str(&answer);
                 self.handle_output(task, result_entry, memory).await;
             }
             Operator::Search => {
@@ -343,6 +602,20 @@ impl Executor {
 
                 let result = result.map_err(|e| ExecutionError::WebSearchFailed(e.to_string()))?;
 
+                // Optional hybrid mode: blend the provider's lexical ranking with semantic
+                // similarity to `query`, reusing the embedder that backs `HaveSimilar`/`search`
+                // so no new model dependency is introduced.
+                let semantic_ratio = search_params["semantic_ratio"]
+                    .as_f64()
+                    .map(|ratio| ratio.clamp(0.0, 1.0) as f32);
+                let result = if let Some(ratio) = semantic_ratio {
+                    self.rerank_search_results_hybrid(&result, &query, ratio, n_results, memory)
+                        .await
+                        .unwrap_or(result)
+                } else {
+                    result
+                };
+
                 log_colored(
                     format!("Operator: {:?}. Output: {:?}", &task.operator, &result).as_str(),
                 );
@@ -412,6 +685,7 @@ impl Executor {
         &self,
         tool_names: Vec<String>,
         custom_templates: Option<Vec<CustomToolTemplate>>,
+        tool_choice: Option<&ToolChoice>,
     ) -> Result<Vec<Arc<dyn Tool>>, ToolError> {
         let mut tools: Vec<Arc<dyn Tool>> = vec![];
 
@@ -446,6 +720,11 @@ impl Executor {
             }
         }
 
+        // force the task to use exactly one tool, if requested
+        if let Some(ToolChoice::Force(name)) = tool_choice {
+            tools.retain(|tool| &tool.name().to_lowercase().replace(' ', "_") == name);
+        }
+
         Ok(tools)
     }
 
@@ -497,97 +776,181 @@ impl Executor {
         }
     }
 
+    /// Builds the `LanguageModelProvider` for the currently selected model, reading whatever
+    /// API key/host that provider needs from the environment at call time (so a per-task
+    /// `Config.model` override, see `Config::model`, always resolves against the right
+    /// credentials).
+    fn language_model_provider(&self, config: &Config) -> Box<dyn LanguageModelProvider + '_> {
+        match self.model.clone().into() {
+            ModelProvider::Ollama => Box::new(OllamaProvider {
+                llm: &self.llm,
+                model: self.model.clone(),
+                max_tokens: config.max_tokens.unwrap_or(250),
+                num_ctx: config.num_ctx,
+                temperature: config.temperature.map(|t| t as f32),
+                top_p: config.top_p,
+                seed: config.seed,
+            }),
+            ModelProvider::OpenAI => {
+                let api_key = std::env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
+                let mut executor = OpenAIExecutor::new(self.model.to_string(), api_key);
+                if let Some(callback) = self.confirmation_callback.clone() {
+                    executor =
+                        executor.with_confirmation_callback(move |name, args| callback(name, args));
+                }
+                Box::new(executor)
+            }
+            ModelProvider::Gemini => {
+                let api_key = std::env::var("GEMINI_API_KEY").expect("$GEMINI_API_KEY is not set");
+                let max_tokens = config.max_tokens.unwrap_or(800);
+                Box::new(GeminiExecutor::new(
+                    self.model.to_string(),
+                    api_key,
+                    max_tokens,
+                ))
+            }
+            ModelProvider::OpenRouter => {
+                let api_key =
+                    std::env::var("OPENROUTER_API_KEY").expect("$OPENROUTER_API_KEY is not set");
+                Box::new(OpenRouterExecutor::new(self.model.to_string(), api_key))
+            }
+        }
+    }
+
     async fn generate_text(
         &self,
         input: Vec<MessageInput>,
         schema: &Option<String>,
         config: &Config,
     ) -> Result<String, OllamaError> {
-        //let json= ChatMessage::assistant(format!("{regex}"));
+        retry_with_backoff(config.max_retries.unwrap_or(0), || {
+            let input = input.clone();
+            async move {
+                self.language_model_provider(config)
+                    .generate_text(input, schema.as_deref())
+                    .await
+            }
+        })
+        .await
+    }
 
-        let mut messages: Vec<ChatMessage> = input
-            .iter()
-            .map(|msg| {
-                match msg.role.as_str() {
-                    "user" => ChatMessage::user(msg.content.clone()),
-                    "assistant" => ChatMessage::assistant(msg.content.clone()),
-                    _ => ChatMessage::user(msg.content.clone()), // fallback to user
+    /// Runs the function-calling flow as a multi-step agent loop: the model is asked for its
+    /// next tool call(s) in raw mode, each tool is executed (concurrently, when the model
+    /// requests more than one in the same turn) and the results are appended back into the chat
+    /// history (the full accumulated history, not just the latest turn, is re-sent to the
+    /// provider on every iteration), then the model is re-queried. This repeats until the model
+    /// answers without requesting another tool or `Config.max_tool_iterations` is reached, with
+    /// the transcript of every step returned alongside the final answer so callers can inspect
+    /// the reasoning chain. Identical `(tool, arguments)` pairs are served from `memory`'s
+    /// memoization cache (shared with `Operator::Generation`'s) so repeated calls to expensive
+    /// tools (e.g. `browserless`, `scraper`) only run once per workflow run, when `Config.cache`
+    /// is on and `task.no_cache` is not set.
+    async fn agentic_function_call(
+        &self,
+        mut prompt: Vec<MessageInput>,
+        task: &Task,
+        memory: &mut ProgramMemory,
+        config: &Config,
+        raw_mode: bool,
+    ) -> Result<(String, Vec<Value>), OllamaError> {
+        let tool_choice = task.tool_choice.as_ref();
+        let max_iterations = config.max_tool_iterations.unwrap_or(5);
+        let tools = self
+            .get_tools(
+                config.tools.clone(),
+                config.custom_tools.clone(),
+                tool_choice,
+            )
+            .map_err(|e| OllamaError::from(e.to_string()))?;
+
+        if matches!(tool_choice, Some(ToolChoice::None)) {
+            let answer = self
+                .function_call_with_choice(prompt, config, raw_mode, tool_choice)
+                .await?;
+            return Ok((answer, Vec::new()));
+        }
+
+        let mut transcript = Vec::new();
+
+        for step in 0..max_iterations {
+            let raw_call = self
+                .function_call_with_choice(prompt.clone(), config, true, tool_choice)
+                .await?;
+
+            let Some(calls) = parse_raw_tool_calls(&raw_call) else {
+                return Ok((raw_call, transcript));
+            };
+
+            // Resolve cache hits up front; only genuine misses need to actually run the tool.
+            let mut results: Vec<Option<String>> = vec![None; calls.len()];
+            let mut pending = Vec::new();
+            for (i, call) in calls.iter().enumerate() {
+                let name = call["name"].as_str().unwrap_or_default().to_string();
+                let arguments = call["arguments"].clone();
+                let digest = (config.cache && !task.no_cache)
+                    .then(|| crate::memory::cache::digest_tool_call(&name, &arguments));
+                match digest.as_deref().and_then(|d| memory.get_memoized(d)) {
+                    Some(cached) => results[i] = Some(cached.to_string()),
+                    None => pending.push((i, name, arguments, digest)),
                 }
-            })
-            .collect();
+            }
 
-        let response = match self.model.clone().into() {
-            ModelProvider::Ollama => {
-                return match self.model {
-                    Model::Llama3_1_8BTextQ4KM
-                    | Model::Llama3_1_8BTextQ8
-                    | Model::Llama3_1_70BTextQ4KM
-                    | Model::Llama3_2_1BTextQ4KM => {
-                        let prompt = input
-                            .last()
-                            .map(|msg| msg.content.as_str())
-                            .unwrap_or_default();
-                        let mut msg =
-                            GenerationRequest::new(self.model.to_string(), prompt.to_string());
-                        let mut ops = GenerationOptions::default();
-                        ops = ops.num_predict(config.max_tokens.unwrap_or(250));
-                        msg = msg.options(ops);
-
-                        let result = self.llm.generate(msg).await?;
-
-                        Ok(result.response)
+            // Run every cache miss concurrently, since independent tool calls in one turn (e.g.
+            // "weather in London and Paris") don't need to be serialized.
+            let run_results =
+                futures::future::join_all(pending.iter().map(|(_, name, arguments, _)| {
+                    let tools = &tools;
+                    async move {
+                        let tool = tools
+                            .iter()
+                            .find(|tool| tool.name().to_lowercase().replace(' ', "_") == *name);
+                        match tool {
+                            Some(tool) => tool
+                                .run(arguments.clone())
+                                .await
+                                .unwrap_or_else(|e| format!("Tool execution failed: {}", e)),
+                            None => format!("Tool `{}` does not exist", name),
+                        }
                     }
-                    _ => {
-                        let mut msg = if let Some(schema) = schema {
-                            let decoded_schema = match BASE64_STANDARD.decode(schema.as_bytes()) {
-                                Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
-                                Err(e) => {
-                                    warn!("Failed to decode base64 schema: {}", e);
-                                    return Err(OllamaError::from(
-                                        "Schema format invalid".to_string(),
-                                    ));
-                                }
-                            };
-                            messages.insert(0, ChatMessage::assistant(decoded_schema.to_string()));
-                            ChatMessageRequest::new(self.model.to_string(), messages)
-                                .format(FormatType::Json)
-                        } else {
-                            ChatMessageRequest::new(self.model.to_string(), messages)
-                        };
-
-                        let mut ops = GenerationOptions::default();
-                        ops = ops.num_predict(config.max_tokens.unwrap_or(250));
-                        msg = msg.options(ops);
+                }))
+                .await;
 
-                        let result = self.llm.send_chat_messages(msg).await?;
-
-                        Ok(result.message.unwrap().content)
-                    }
+            for ((i, _name, _arguments, digest), result) in pending.into_iter().zip(run_results) {
+                if let Some(digest) = digest {
+                    memory.set_memoized(digest, Entry::String(result.clone()));
                 }
+                results[i] = Some(result);
             }
-            ModelProvider::OpenAI => {
-                let api_key = std::env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
 
-                let openai_executor = OpenAIExecutor::new(self.model.to_string(), api_key.clone());
-                openai_executor.generate_text(input, schema).await?
+            let mut result_lines = Vec::with_capacity(calls.len());
+            for (call, result) in calls.iter().zip(results) {
+                let name = call["name"].as_str().unwrap_or_default().to_string();
+                let arguments = call["arguments"].clone();
+                let result = result.expect("every call was resolved from cache or just run");
+                transcript.push(json!({
+                    "step": step,
+                    "tool": name,
+                    "arguments": arguments,
+                    "result": result,
+                }));
+                result_lines.push(format!("{}: {}", name, result));
             }
-            ModelProvider::Gemini => {
-                let api_key = std::env::var("GEMINI_API_KEY").expect("$GEMINI_API_KEY is not set");
-                let max_tokens = config.max_tokens.unwrap_or(800);
-                let executor = GeminiExecutor::new(self.model.to_string(), api_key, max_tokens);
-                executor.generate_text(input, schema).await?
-            }
-            ModelProvider::OpenRouter => {
-                let api_key =
-                    std::env::var("OPENROUTER_API_KEY").expect("$OPENROUTER_API_KEY is not set");
 
-                let openai_executor =
-                    OpenRouterExecutor::new(self.model.to_string(), api_key.clone());
-                openai_executor.generate_text(input, schema).await?
-            }
-        };
+            prompt.push(MessageInput::new_assistant_message(raw_call));
+            prompt.push(MessageInput::new_user_message(format!(
+                "Tool results:\n{}",
+                result_lines.join("\n")
+            )));
+        }
 
-        Ok(response)
+        warn!(
+            "Reached max_tool_iterations ({}) without a final answer, asking once more",
+            max_iterations
+        );
+        let final_answer = self
+            .function_call_with_choice(prompt, config, raw_mode, tool_choice)
+            .await?;
+        Ok((final_answer, transcript))
     }
 
     async fn function_call(
@@ -595,83 +958,44 @@ impl Executor {
         input: Vec<MessageInput>,
         config: &Config,
         raw_mode: bool,
+    ) -> Result<String, OllamaError> {
+        self.function_call_with_choice(input, config, raw_mode, None)
+            .await
+    }
+
+    async fn function_call_with_choice(
+        &self,
+        input: Vec<MessageInput>,
+        config: &Config,
+        raw_mode: bool,
+        tool_choice: Option<&ToolChoice>,
     ) -> Result<String, OllamaError> {
         let oai_parser = Arc::new(OpenAIFunctionCall {});
-        let llama_parser = Arc::new(LlamaFunctionCall {});
         let tools = self
-            .get_tools(config.tools.clone(), config.custom_tools.clone())
+            .get_tools(
+                config.tools.clone(),
+                config.custom_tools.clone(),
+                tool_choice,
+            )
             .unwrap();
 
-        let prompt = input
-            .last()
-            .map(|msg| msg.content.as_str())
-            .unwrap_or_default();
-
-        let result = match self.model.clone().into() {
-            ModelProvider::Ollama => {
-                //if raw mode is enabled, return only the calls
-                let mut request = FunctionCallRequest::new(
-                    self.model.to_string(),
-                    tools.clone(),
-                    vec![ChatMessage::user(prompt.to_string())],
-                );
-
-                if raw_mode {
-                    request = request.raw_mode();
-                }
-
-                let res = self
-                    .llm
-                    .send_function_call(
-                        request,
-                        match self.model {
-                            Model::NousTheta => llama_parser.clone(),
-                            Model::Llama3_1_8B
-                            | Model::Llama3_1_8Bf16
-                            | Model::Llama3_1_8Bq8
-                            | Model::Llama3_2_3B
-                            | Model::Llama3_1_70Bq8
-                            | Model::Llama3_1_70B => llama_parser.clone(),
-                            _ => oai_parser.clone(),
-                        },
-                    )
-                    .await?;
-                res.message.unwrap().content
+        if let ModelProvider::Gemini = self.model.clone().into() {
+            if !matches!(self.model, Model::Gemini15Flash | Model::Gemini15Pro) {
+                return Err(OllamaError::from(format!("Gemini doesn't support function calling for {}. Try using either: Gemini15Flash or Gemini15Pro", self.model)));
             }
-            ModelProvider::OpenAI => {
-                let api_key = std::env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
-
-                let openai_executor = OpenAIExecutor::new(self.model.to_string(), api_key.clone());
-                openai_executor
-                    .function_call(prompt, tools, raw_mode, oai_parser)
-                    .await?
-            }
-            ModelProvider::Gemini => {
-                let api_key = std::env::var("GEMINI_API_KEY").expect("$GEMINI_API_KEY is not set");
-                let max_tokens = config.max_tokens.unwrap_or(800);
-                match self.model{
-                    Model::Gemini15Flash | Model::Gemini15Pro => {
-                        let executor = GeminiExecutor::new(self.model.to_string(), api_key, max_tokens);
-                        executor
-                            .function_call(prompt, tools, raw_mode, oai_parser)
-                            .await?
-                    }
-                    _ => return Err(OllamaError::from(format!("Gemini doesn't support function calling for {}. Try using either: Gemini15Flash or Gemini15Pro", self.model)))
-                }
-            }
-            ModelProvider::OpenRouter => {
-                let api_key =
-                    std::env::var("OPENROUTER_API_KEY").expect("$OPENROUTER_API_KEY is not set");
+        }
 
-                let openai_executor =
-                    OpenRouterExecutor::new(self.model.to_string(), api_key.clone());
-                openai_executor
-                    .function_call(prompt, tools, raw_mode, oai_parser)
-                    .await?
+        retry_with_backoff(config.max_retries.unwrap_or(0), || {
+            let input = input.clone();
+            let tools = tools.clone();
+            let oai_parser = oai_parser.clone();
+            async move {
+                self.language_model_provider(config)
+                    .function_call(input, tools, raw_mode, oai_parser, tool_choice)
+                    .await
             }
-        };
-
-        Ok(result)
+        })
+        .await
     }
 
     /// Lists existing models compatible with the `Model` enum.
@@ -705,6 +1029,140 @@ impl Executor {
         Ok(())
     }
 
+    /// Warms up the selected model and, as a side effect, checks that its provider is actually
+    /// reachable before a workflow starts relying on it. For `ModelProvider::Ollama`, issues an
+    /// empty-prompt generate request, which makes Ollama load the model's weights into memory
+    /// (avoiding the usual slow first inference) and surfaces a connection error immediately if
+    /// the server is down, instead of partway through a workflow. Cloud providers have no
+    /// comparable "load the weights" step, so this only validates that the provider's API key is
+    /// set, giving callers one uniform "is this executor ready?" check regardless of provider.
+    pub async fn preload_model(&self) -> Result<(), OllamaError> {
+        match ModelProvider::from(self.model.clone()) {
+            ModelProvider::Ollama => {
+                let request = GenerationRequest::new(self.model.to_string(), String::new());
+                self.llm.generate(request).await?;
+                Ok(())
+            }
+            ModelProvider::OpenAI => {
+                std::env::var("OPENAI_API_KEY")
+                    .map_err(|_| OllamaError::from("$OPENAI_API_KEY is not set".to_string()))?;
+                Ok(())
+            }
+            ModelProvider::Gemini => {
+                std::env::var("GEMINI_API_KEY")
+                    .map_err(|_| OllamaError::from("$GEMINI_API_KEY is not set".to_string()))?;
+                Ok(())
+            }
+            ModelProvider::OpenRouter => {
+                std::env::var("OPENROUTER_API_KEY").map_err(|_| {
+                    OllamaError::from("$OPENROUTER_API_KEY is not set".to_string())
+                })?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Embeds `input` with the selected model via `RestEmbedder`, dispatching to
+    /// `OllamaRestEmbedder` or `OpenAICompatibleEmbedder` depending on `self.model`'s provider.
+    /// Only Ollama and OpenAI models are supported; Gemini and OpenRouter models return an error.
+    /// The embedder is rebuilt (and its dimension re-probed) on every call rather than cached on
+    /// `Executor`, matching `language_model_provider`'s own per-call construction; the model is
+    /// never auto-pulled, so an Ollama embedding model that isn't present locally surfaces as an
+    /// error instead of silently downloading one.
+    pub async fn generate_embeddings(
+        &self,
+        input: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, OllamaError> {
+        match ModelProvider::from(self.model.clone()) {
+            ModelProvider::Ollama => {
+                let embedder = OllamaRestEmbedder::new(
+                    format!("{}:{}", self.ollama_host, self.ollama_port),
+                    self.model.to_string(),
+                    8,
+                )
+                .await
+                .map_err(|e| OllamaError::from(e.to_string()))?;
+                embedder
+                    .embed_chunks(&input)
+                    .await
+                    .map_err(|e| OllamaError::from(e.to_string()))
+            }
+            ModelProvider::OpenAI => {
+                let api_key = std::env::var("OPENAI_API_KEY").expect("$OPENAI_API_KEY is not set");
+                let embedder = OpenAICompatibleEmbedder::new(
+                    "https://api.openai.com".to_string(),
+                    api_key,
+                    self.model.to_string(),
+                    8,
+                )
+                .await
+                .map_err(|e| OllamaError::from(e.to_string()))?;
+                embedder
+                    .embed_chunks(&input)
+                    .await
+                    .map_err(|e| OllamaError::from(e.to_string()))
+            }
+            provider @ (ModelProvider::Gemini | ModelProvider::OpenRouter) => Err(
+                OllamaError::from(format!("embeddings are not supported for {:?}", provider)),
+            ),
+        }
+    }
+
+    /// Re-ranks a JSON array of `{title, link, snippet}` search results (as produced by
+    /// `RawSearchTool`/`RawDDGSearcher`) by `final = (1 - ratio) * lexical + ratio * semantic`,
+    /// where `lexical` is `1 - rank / len` for each result's position in the lexical list and
+    /// `semantic` is its embedding cosine similarity to `query`, each divided by its maximum over
+    /// the candidate pool before fusing. Returns `None` (letting the caller fall back to the lexical-only
+    /// result) if the response isn't a JSON array or embedding any candidate fails.
+    async fn rerank_search_results_hybrid(
+        &self,
+        raw_results: &str,
+        query: &str,
+        ratio: f32,
+        n_results: Option<u64>,
+        memory: &ProgramMemory,
+    ) -> Option<String> {
+        let items: Vec<Value> = serde_json::from_str(raw_results).ok()?;
+        if items.is_empty() {
+            return Some(raw_results.to_string());
+        }
+
+        let mut semantic_scores = Vec::with_capacity(items.len());
+        for item in &items {
+            let text = format!(
+                "{} {}",
+                item["title"].as_str().unwrap_or_default(),
+                item["snippet"].as_str().unwrap_or_default()
+            );
+            semantic_scores.push(memory.semantic_similarity(query, &text).await?);
+        }
+
+        let len = items.len() as f32;
+        let lexical_scores: Vec<f32> = (0..items.len())
+            .map(|rank| 1.0 - (rank as f32 / len))
+            .collect();
+
+        let lex_max = lexical_scores.iter().cloned().fold(f32::EPSILON, f32::max);
+        let sem_max = semantic_scores.iter().cloned().fold(f32::EPSILON, f32::max);
+
+        let mut scored: Vec<(usize, f32)> = (0..items.len())
+            .map(|i| {
+                let fused = (1.0 - ratio) * (lexical_scores[i] / lex_max)
+                    + ratio * (semantic_scores[i] / sem_max);
+                (i, fused)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        let n = n_results.unwrap_or(items.len() as u64) as usize;
+        let reranked: Vec<Value> = scored
+            .into_iter()
+            .take(n)
+            .map(|(i, _)| items[i].clone())
+            .collect();
+        serde_json::to_string(&reranked).ok()
+    }
+
     //randomly sample list of entries
     fn sample(&self, entries: &[Entry]) -> Entry {
         let index = rand::thread_rng().gen_range(0..entries.len());
@@ -728,4 +1186,31 @@ mod tests {
 
         executor.pull_model().await.expect("should pull model");
     }
+
+    #[test]
+    fn parse_raw_tool_calls_reads_a_single_call() {
+        let calls = parse_raw_tool_calls(r#"{"name": "scraper", "arguments": {"url": "x"}}"#)
+            .expect("should parse one call");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0]["name"], "scraper");
+    }
+
+    #[test]
+    fn parse_raw_tool_calls_reads_several_blank_line_separated_calls() {
+        let raw = "{\"name\": \"a\", \"arguments\": {}}\n\n{\"name\": \"b\", \"arguments\": {}}";
+        let calls = parse_raw_tool_calls(raw).expect("should parse both calls");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0]["name"], "a");
+        assert_eq!(calls[1]["name"], "b");
+    }
+
+    #[test]
+    fn parse_raw_tool_calls_treats_non_json_as_a_final_answer() {
+        assert!(parse_raw_tool_calls("The answer is 42.").is_none());
+    }
+
+    #[test]
+    fn parse_raw_tool_calls_treats_empty_input_as_a_final_answer() {
+        assert!(parse_raw_tool_calls("   \n\n  ").is_none());
+    }
 }
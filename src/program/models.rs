@@ -43,10 +43,10 @@ pub enum Model {
     /// [Meta's Llama3.1 model fp16](https://ollama.com/library/llama3.1:8b-instruct-fp16)
     #[serde(rename = "llama3.1:8b-instruct-fp16")]
     Llama3_1_8Bf16,
-    /// 
+    ///
     #[serde(rename = "llama3.1:70b-instruct-q4_0")]
     Llama3_1_70B,
-    /// 
+    ///
     #[serde(rename = "llama3.1:70b-instruct-q8_0")]
     Llama3_1_70Bq8,
     /// [Alibaba's Qwen2 model](https://ollama.com/library/qwen2), 7B parameters
@@ -58,6 +58,10 @@ pub enum Model {
     /// []
     #[serde(rename = "qwen2.5:32b-instruct-fp16")]
     Qwen2_5_32Bf16,
+    /// [Nomic's embedding model](https://ollama.com/library/nomic-embed-text), for
+    /// `Executor::generate_embeddings`. Doesn't support chat/tool calling.
+    #[serde(rename = "nomic-embed-text")]
+    NomicEmbedText,
     // OpenAI models
     /// [OpenAI's GPT-4 Turbo model](https://platform.openai.com/docs/models/gpt-4-turbo-and-gpt-4)
     #[serde(rename = "gpt-4-turbo")]
@@ -74,11 +78,55 @@ pub enum Model {
     /// [OpenAI's o1 preview model](https://platform.openai.com/docs/models/o1)
     #[serde(rename = "o1-preview")]
     O1Preview,
+    /// [OpenAI's text-embedding-3-small model](https://platform.openai.com/docs/guides/embeddings),
+    /// for `Executor::generate_embeddings`. Doesn't support chat/tool calling.
+    #[serde(rename = "text-embedding-3-small")]
+    TextEmbedding3Small,
+    // Gemini models
+    /// [Google's Gemini 1.5 Flash model](https://ai.google.dev/gemini-api/docs/models/gemini#gemini-1.5-flash)
+    #[serde(rename = "gemini-1.5-flash")]
+    Gemini15Flash,
+    /// [Google's Gemini 1.5 Pro model](https://ai.google.dev/gemini-api/docs/models/gemini#gemini-1.5-pro)
+    #[serde(rename = "gemini-1.5-pro")]
+    Gemini15Pro,
+    // OpenRouter models
+    /// [OpenAI's o1 model, routed through OpenRouter](https://openrouter.ai/openai/o1), exposed
+    /// for its `<think>`-wrapped reasoning traces; see `Model::has_reasoning`.
+    #[serde(rename = "openai/o1")]
+    ORR1,
+    /// A model not otherwise known to this crate. The `name` is passed through verbatim to
+    /// `provider`, so workflows can track fast-moving model catalogs without a crate release.
+    /// Since we can't infer tool-calling support for an arbitrary model, it must be declared
+    /// by the caller.
+    #[serde(rename = "custom")]
+    Custom {
+        name: String,
+        provider: ModelProvider,
+        #[serde(default)]
+        supports_tool_calling: bool,
+    },
 }
 
 impl Model {
+    /// Creates a `Model::Custom` for a model name this crate doesn't know about.
+    pub fn custom(
+        name: impl Into<String>,
+        provider: ModelProvider,
+        supports_tool_calling: bool,
+    ) -> Self {
+        Model::Custom {
+            name: name.into(),
+            provider,
+            supports_tool_calling,
+        }
+    }
+
     pub fn supports_tool_calling(&self) -> bool {
         match self {
+            Model::Custom {
+                supports_tool_calling,
+                ..
+            } => *supports_tool_calling,
             // OpenAI models that support tool calling
             Model::GPT4Turbo | Model::GPT4o | Model::GPT4oMini => true,
             // Ollama models that support tool calling
@@ -90,10 +138,25 @@ impl Model {
             | Model::Gemma2_9BFp16
             | Model::Qwen2_5_7B
             | Model::Qwen2_5_7Bf16 => true,
-            | Model::Qwen2_5_32Bf16 => true,
+            Model::Qwen2_5_32Bf16 => true,
+            // Gemini models that support tool calling
+            Model::Gemini15Flash | Model::Gemini15Pro => true,
             _ => false,
         }
     }
+
+    /// The `ModelProvider` that hosts this model; equivalent to `.clone().into()` but more
+    /// readable at call sites that don't need to consume `self`.
+    pub fn provider(&self) -> ModelProvider {
+        self.clone().into()
+    }
+
+    /// Whether this model streams its reasoning trace wrapped in `<think>...</think>` before
+    /// its answer (see `sse_content_stream`). Currently only true for OpenRouter models that
+    /// expose a `reasoning` request field (see `OpenRouterReasoning`).
+    pub fn has_reasoning(&self) -> bool {
+        matches!(self, Model::ORR1)
+    }
 }
 
 impl From<Model> for String {
@@ -104,6 +167,11 @@ impl From<Model> for String {
 
 impl fmt::Display for Model {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // custom models carry their own name rather than a fixed serde rename
+        if let Model::Custom { name, .. } = self {
+            return write!(f, "{}", name);
+        }
+
         // guaranteed not to fail because this is enum to string serialization
         let self_str = serde_json::to_string(&self).unwrap_or_default();
 
@@ -136,6 +204,10 @@ pub enum ModelProvider {
     Ollama,
     #[serde(rename = "openai")]
     OpenAI,
+    #[serde(rename = "gemini")]
+    Gemini,
+    #[serde(rename = "openrouter")]
+    OpenRouter,
 }
 
 impl From<Model> for ModelProvider {
@@ -156,11 +228,17 @@ impl From<Model> for ModelProvider {
             Model::Qwen2_5_7B => ModelProvider::Ollama,
             Model::Qwen2_5_7Bf16 => ModelProvider::Ollama,
             Model::Qwen2_5_32Bf16 => ModelProvider::Ollama,
+            Model::NomicEmbedText => ModelProvider::Ollama,
             Model::GPT4Turbo => ModelProvider::OpenAI,
             Model::GPT4o => ModelProvider::OpenAI,
             Model::GPT4oMini => ModelProvider::OpenAI,
             Model::O1Mini => ModelProvider::OpenAI,
             Model::O1Preview => ModelProvider::OpenAI,
+            Model::TextEmbedding3Small => ModelProvider::OpenAI,
+            Model::Gemini15Flash => ModelProvider::Gemini,
+            Model::Gemini15Pro => ModelProvider::Gemini,
+            Model::ORR1 => ModelProvider::OpenRouter,
+            Model::Custom { provider, .. } => provider,
         }
     }
 }
@@ -242,4 +320,45 @@ mod tests {
             serde_json::from_str::<ModelProvider>("\"this-provider-does-not-will-not-exist\"");
         assert!(bad_provider.is_err());
     }
+
+    #[test]
+    fn test_custom_model() {
+        let model = Model::custom("some-brand-new-model", ModelProvider::OpenAI, true);
+
+        assert_eq!(model.to_string(), "some-brand-new-model");
+        assert!(model.supports_tool_calling());
+        assert_eq!(ModelProvider::from(model.clone()), ModelProvider::OpenAI);
+
+        // round-trips through serde as a tagged object, not a bare string
+        let model_json = serde_json::to_string(&model).expect("should serialize");
+        let model_from: Model = serde_json::from_str(&model_json).expect("should deserialize");
+        assert_eq!(model_from, model);
+    }
+
+    #[test]
+    fn test_embedding_models_route_to_the_right_provider() {
+        assert_eq!(
+            ModelProvider::from(Model::NomicEmbedText),
+            ModelProvider::Ollama
+        );
+        assert_eq!(
+            ModelProvider::from(Model::TextEmbedding3Small),
+            ModelProvider::OpenAI
+        );
+        // Embedding models aren't chat/tool-calling models.
+        assert!(!Model::NomicEmbedText.supports_tool_calling());
+        assert!(!Model::TextEmbedding3Small.supports_tool_calling());
+    }
+
+    #[test]
+    fn test_gemini_and_openrouter_models_route_to_their_provider() {
+        assert_eq!(Model::Gemini15Flash.provider(), ModelProvider::Gemini);
+        assert_eq!(Model::Gemini15Pro.provider(), ModelProvider::Gemini);
+        assert!(Model::Gemini15Flash.supports_tool_calling());
+        assert!(Model::Gemini15Pro.supports_tool_calling());
+
+        assert_eq!(Model::ORR1.provider(), ModelProvider::OpenRouter);
+        assert!(Model::ORR1.has_reasoning());
+        assert!(!Model::Gemini15Flash.has_reasoning());
+    }
 }
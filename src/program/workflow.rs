@@ -1,9 +1,22 @@
-use super::atomics::{Config, Edge, Task, TaskOutput};
+use super::atomics::{Config, Edge, Task, TaskOutput, TaskOutputInput, R_END};
+use super::errors::WorkflowError;
+use super::io::InputValueType;
+use super::parser::{parse_step, parse_task_with_diagnostics};
 use crate::memory::types::{Entry, StackPage, ID};
 use serde::{Deserialize, Deserializer};
 use serde_json::Value;
-use std::collections::HashMap;
-use super::parser::{parse_step,lexer_tasks,parse_tasks};
+use std::collections::{HashMap, HashSet};
+
+/// Outcome of a single task during `Executor::execute_traced`, recorded against the task's `id`
+/// and used by `Workflow::to_dot_with_trace` to color the rendered graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    /// The task ran and `execute_task` returned `Ok`.
+    Visited,
+    /// The task ran and `execute_task` returned `Err` (the run then took its `fallback` edge,
+    /// if one was set).
+    Failed,
+}
 
 fn split_json_string(s: &str) -> Vec<String> {
     let substrings: Vec<&str> = s.split("},{").collect();
@@ -57,36 +70,30 @@ where
     }
 }
 
-fn deserialize_tasks<'de, D>(
-    deserializer: D,
-) -> Result<Vec<Task>, D::Error>
+fn deserialize_tasks<'de, D>(deserializer: D) -> Result<Vec<Task>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    
     let value: Option<Value> = Option::deserialize(deserializer)?;
     let mut string_representation = String::new();
-        // Convert the entire deserialized object into a string representation
+    // Convert the entire deserialized object into a string representation
     string_representation = serde_json::to_string(&value).unwrap();
-   // error handling
+    // error handling
     let trimmed_string = string_representation
-    .trim_start_matches('[')
-    .trim_end_matches(']');
+        .trim_start_matches('[')
+        .trim_end_matches(']');
     let split_strings = split_json_string(trimmed_string);
     let mut tasks = Vec::<Task>::new();
-    for lines in split_strings {
-       let tokens = lexer_tasks(&lines);
-       let task = parse_tasks(tokens);
-       tasks.push(task)
+    for block in split_strings {
+        let task = parse_task_with_diagnostics(&block)
+            .map_err(|e| serde::de::Error::custom(e.render(&block)))?;
+        tasks.push(task)
     }
 
-   
     Ok(tasks)
 }
 
-fn deserialize_steps<'de, D>(
-    deserializer: D,
-) -> Result<Vec<Edge>, D::Error>
+fn deserialize_steps<'de, D>(deserializer: D) -> Result<Vec<Edge>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -101,7 +108,6 @@ where
     let lines: Vec<&str> = multi_line_string.split('.').filter(predicate).collect();
 
     let mut steps = Vec::<Edge>::new();
-      
 
     for line in lines {
         if !line.trim().is_empty() {
@@ -110,11 +116,11 @@ where
             steps.push(step);
         }
     }
-        
+
     Ok(steps)
-   
+
     // } else {
-    //     Ok(None) 
+    //     Ok(None)
     // maybe not required as we are returning empty vec
     // }
 }
@@ -188,4 +194,220 @@ impl Workflow {
     pub fn get_tasks_by_id(&self, task_id: &str) -> Option<&Task> {
         self.tasks.iter().find(|task| task.id == task_id)
     }
+
+    /// Serializes `tasks` and `steps` back into `.workflow` DSL text, in the same
+    /// task-blocks-then-`---`-then-steps layout `parser::parse_workflow_incremental` reads,
+    /// via `Task::to_dsl`/`Edge::to_dsl`.
+    pub fn serialize(&self) -> String {
+        let tasks: Vec<String> = self.tasks.iter().map(Task::to_dsl).collect();
+        let steps: Vec<String> = self.steps.iter().map(Edge::to_dsl).collect();
+        format!("{}\n---\n{}", tasks.join("\n"), steps.join("\n"))
+    }
+
+    /// Performs a static dataflow analysis over `tasks` and `steps` and reports structural
+    /// problems before the workflow is ever executed: dangling edges, tasks unreachable from
+    /// the entry step, cycles with no conditional edge capable of breaking out of them, and
+    /// "dead" outputs written to memory keys that no task's `inputs` and not the return value
+    /// ever reads.
+    pub fn validate(&self) -> Result<(), Vec<WorkflowError>> {
+        let mut errors = Vec::new();
+        let task_ids: HashSet<&str> = self.tasks.iter().map(|t| t.id.as_str()).collect();
+
+        // 1. dangling references
+        for edge in &self.steps {
+            for referenced in [
+                Some(edge.source.as_str()),
+                Some(edge.target.as_str()),
+                edge.fallback.as_deref(),
+                edge.condition.as_ref().map(|c| c.target_if_not.as_str()),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if referenced != R_END && !task_ids.contains(referenced) {
+                    errors.push(WorkflowError::DanglingReference {
+                        edge_source: edge.source.clone(),
+                        missing_task: referenced.to_string(),
+                    });
+                }
+            }
+        }
+
+        // 2. reachability from the entry step
+        let mut reached: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = self
+            .get_step(0)
+            .map(|e| e.source.as_str())
+            .into_iter()
+            .collect();
+        while let Some(id) = stack.pop() {
+            if !reached.insert(id) {
+                continue;
+            }
+            for edge in self.steps.iter().filter(|e| e.source == id) {
+                stack.push(&edge.target);
+                if let Some(condition) = &edge.condition {
+                    stack.push(&condition.target_if_not);
+                }
+                if let Some(fallback) = &edge.fallback {
+                    stack.push(fallback);
+                }
+            }
+        }
+        for task in &self.tasks {
+            if !reached.contains(task.id.as_str()) {
+                errors.push(WorkflowError::UnreachableTask(task.id.clone()));
+            }
+        }
+
+        // 3. cycles without any conditional edge to break out of them
+        for edge in &self.steps {
+            if edge.condition.is_some() {
+                continue;
+            }
+            if let Some(cycle) = self.find_unconditional_cycle(&edge.source) {
+                errors.push(WorkflowError::UnterminatedCycle(cycle));
+            }
+        }
+
+        // 4. dead outputs: keys written but never read by any input or the return value
+        let read_keys: HashSet<&str> = self
+            .tasks
+            .iter()
+            .flat_map(|t| &t.inputs)
+            .filter(|input| {
+                matches!(
+                    input.value.value_type,
+                    InputValueType::Read
+                        | InputValueType::Peek
+                        | InputValueType::GetAll
+                        | InputValueType::Pop
+                )
+            })
+            .map(|input| input.value.key.as_str())
+            .chain(self.return_value_keys())
+            .collect();
+
+        for task in &self.tasks {
+            for output in &task.outputs {
+                if !read_keys.contains(output.key.as_str()) {
+                    errors.push(WorkflowError::DeadOutput {
+                        task_id: task.id.clone(),
+                        key: output.key.clone(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the memory keys read by the workflow's return value, so they count as "live".
+    fn return_value_keys(&self) -> Vec<&str> {
+        match &self.return_value.input {
+            TaskOutputInput::Single(input) => vec![input.key.as_str()],
+            TaskOutputInput::Multiple(inputs) => inputs.iter().map(|i| i.key.as_str()).collect(),
+        }
+    }
+
+    /// Depth-first search for a cycle reachable from `start` that only traverses
+    /// edges without a `Condition` (a conditional edge always has an escape route). An edge
+    /// with only a `fallback` counts as unconditional and is followed.
+    fn find_unconditional_cycle(&self, start: &str) -> Option<Vec<String>> {
+        let mut path = vec![start.to_string()];
+        let mut visited: HashSet<String> = HashSet::from([start.to_string()]);
+        let mut current = start.to_string();
+
+        loop {
+            let next = self
+                .steps
+                .iter()
+                .find(|e| e.source == current && e.condition.is_none())
+                .map(|e| e.target.clone())?;
+
+            if next == start {
+                path.push(next);
+                return Some(path);
+            }
+            if !visited.insert(next.clone()) {
+                return None;
+            }
+            path.push(next.clone());
+            current = next;
+        }
+    }
+
+    /// Renders the task/edge graph as a Graphviz `digraph` for visualization, e.g. by piping
+    /// the output into `dot -Tpng`. Each task becomes a node labeled with its `id`/`name` and
+    /// `Operator`; each edge becomes a directed `source -> target` edge. Conditional edges are
+    /// labeled with their `Expression`/`expected` value, and the `target_if_not`/`fallback`
+    /// branch is rendered as a separate dashed edge so conditional routing is visible at a glance.
+    pub fn to_dot(&self) -> String {
+        self.render_dot(None)
+    }
+
+    /// Same as `to_dot`, but colors each task node by its outcome in `trace` (as recorded by
+    /// `Executor::execute_traced`): green for a task that ran successfully, red for one that
+    /// failed, and gray for a task the run never reached. Useful for seeing at a glance why a
+    /// workflow took the path it did, especially around `HaveSimilar` conditions and fallback
+    /// routing that otherwise only show up as scattered `warn!` logs.
+    pub fn to_dot_with_trace(&self, trace: &HashMap<String, TaskStatus>) -> String {
+        self.render_dot(Some(trace))
+    }
+
+    fn render_dot(&self, trace: Option<&HashMap<String, TaskStatus>>) -> String {
+        let mut dot = String::from("digraph Workflow {\n");
+
+        for task in &self.tasks {
+            let label = if task.name.is_empty() {
+                format!("{} [{:?}]", task.id, task.operator)
+            } else {
+                format!("{}: {} [{:?}]", task.id, task.name, task.operator)
+            };
+            let style = match trace.map(|t| t.get(&task.id)) {
+                Some(Some(TaskStatus::Visited)) => ", style=filled, fillcolor=\"#c6efce\"",
+                Some(Some(TaskStatus::Failed)) => ", style=filled, fillcolor=\"#ffc7ce\"",
+                Some(None) => ", style=filled, fillcolor=\"#eeeeee\"",
+                None => "",
+            };
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\"{}];\n",
+                task.id,
+                label.replace('"', "\\\""),
+                style
+            ));
+        }
+
+        for edge in &self.steps {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\";\n",
+                edge.source, edge.target
+            ));
+
+            if let Some(condition) = &edge.condition {
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{:?} {}\"];\n",
+                    edge.source, edge.target, condition.expression, condition.expected
+                ));
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [style=dashed, label=\"else\"];\n",
+                    edge.source, condition.target_if_not
+                ));
+            }
+
+            if let Some(fallback) = &edge.fallback {
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [style=dashed, label=\"fallback\"];\n",
+                    edge.source, fallback
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
 }